@@ -6,9 +6,42 @@ mod tools;
 #[pymodule]
 fn gdrs(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<models::PyGenomeAssembly>()?;
+    m.add_class::<models::PyChromSizes>()?;
+    m.add_class::<models::PyAssemblyGuess>()?;
+    m.add_class::<models::PyAssemblyComparison>()?;
+    m.add_class::<models::PyGcBackground>()?;
+    m.add_class::<models::PyLengthSummary>()?;
     m.add_function(wrap_pyfunction!(tools::py_calc_widths, m)?)?;
     m.add_function(wrap_pyfunction!(tools::py_calc_gc_content, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_calc_gc_content_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_calc_n_fraction, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_calc_gc_content_ref, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_calc_repeat_fraction, m)?)?;
     m.add_function(wrap_pyfunction!(tools::py_calc_neighbor_distances, m)?)?;
     m.add_function(wrap_pyfunction!(tools::py_calc_dinucleotide_frequency, m)?)?;
+    m.add_function(wrap_pyfunction!(
+        tools::py_calc_dinucleotide_frequency_per_region,
+        m
+    )?)?;
+    m.add_function(wrap_pyfunction!(tools::py_calc_kmer_frequency, m)?)?;
+    m.add_function(wrap_pyfunction!(
+        tools::py_calc_kmer_frequency_per_region,
+        m
+    )?)?;
+    m.add_function(wrap_pyfunction!(tools::py_calc_cpg_oe, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_calc_complexity, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_find_cpg_islands, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_find_gaps, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_digest_genome, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_map_to_fragments, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_fragment_length_summary, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_extract_sequences, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_one_hot_encode, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_write_fasta, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_scan_motifs, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_calc_motif_enrichment, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_search_patterns, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_detect_assembly, m)?)?;
+    m.add_function(wrap_pyfunction!(tools::py_region_set_digest, m)?)?;
     Ok(())
 }
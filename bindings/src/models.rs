@@ -1,19 +1,255 @@
+use gdrs::models::{AssemblyGuess, GcBackground, LengthSummary};
 use gdrs::prelude::*;
 use pyo3::prelude::*;
 
 use std::path::Path;
 
-#[pyclass(name = "GenomeAssembly")]
+#[pyclass(name = "GenomeAssembly", module = "gdrs")]
 pub struct PyGenomeAssembly {
     pub genome_assembly: GenomeAssembly,
+    // absolute paths the genome was opened from, so pickling only sends paths
+    path: String,
+    chrom_aliases: Option<String>,
+}
+
+// an absolute path, so the genome reopens from any working directory
+fn absolute_path(path: &str) -> String {
+    std::fs::canonicalize(path)
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| path.to_string())
 }
 
 #[pymethods]
 impl PyGenomeAssembly {
     #[new]
-    pub fn new(path: String) -> Self {
+    #[pyo3(signature = (path, chrom_aliases=None))]
+    pub fn new(path: String, chrom_aliases: Option<String>) -> anyhow::Result<Self> {
+        let aliases = match &chrom_aliases {
+            Some(chrom_aliases) => ChromAliases::from_file(Path::new(chrom_aliases))?,
+            None => ChromAliases::builtin(),
+        };
+        let genome_assembly =
+            GenomeAssembly::from_file(Path::new(&path))?.with_aliases(&aliases)?;
+        Ok(PyGenomeAssembly {
+            genome_assembly,
+            path: absolute_path(&path),
+            chrom_aliases: chrom_aliases.as_deref().map(absolute_path),
+        })
+    }
+
+    /// Pickle by path: workers reopen the file, and a memory-mapped genome
+    /// is then shared with every other process that has it open.
+    pub fn __reduce__(slf: &Bound<'_, Self>) -> PyResult<(PyObject, (String, Option<String>))> {
+        let py = slf.py();
+        let genome = slf.borrow();
+        Ok((
+            slf.get_type().into_py(py),
+            (genome.path.to_string(), genome.chrom_aliases.clone()),
+        ))
+    }
+
+    #[getter]
+    pub fn path(&self) -> String {
+        self.path.to_string()
+    }
+
+    /// Write the genome as a .2bit file ("2bit"), or as a memory-mapped
+    /// genome shared across processes ("mmap"), with its digests in
+    /// `<output>.seqcol`.
+    #[pyo3(signature = (output, format="2bit"))]
+    pub fn pack(&self, output: String, format: &str) -> anyhow::Result<()> {
+        pack_genome(
+            &self.genome_assembly,
+            Path::new(&self.path),
+            Path::new(&output),
+            format.parse::<GenomeFormat>()?,
+        )
+    }
+
+    pub fn chrom_sizes(&self) -> PyChromSizes {
+        PyChromSizes {
+            chrom_sizes: self.genome_assembly.chrom_sizes(),
+        }
+    }
+
+    /// (name, length, refget digest) of every chromosome, in file order.
+    pub fn refget_digests(&self) -> anyhow::Result<Vec<(String, u32, String)>> {
+        let collection = self.collection()?;
+        Ok(collection
+            .names
+            .into_iter()
+            .zip(collection.lengths)
+            .zip(collection.sequences)
+            .map(|((name, length), seq)| (name, length, seq))
+            .collect())
+    }
+
+    /// The GA4GH sequence collection digest of the whole genome.
+    pub fn seqcol_digest(&self) -> anyhow::Result<String> {
+        Ok(self.collection()?.digest())
+    }
+
+    /// Compare chromosomes by name against another GenomeAssembly, or only
+    /// their lengths against a ChromSizes.
+    pub fn compare(&self, other: &Bound<'_, PyAny>) -> anyhow::Result<PyAssemblyComparison> {
+        let collection = self.collection()?;
+        let comparison = if let Ok(other) = other.downcast::<PyGenomeAssembly>() {
+            collection.compare(&other.borrow().collection()?)
+        } else if let Ok(other) = other.downcast::<PyChromSizes>() {
+            collection.compare_sizes(&other.borrow().chrom_sizes)
+        } else {
+            return Err(anyhow::anyhow!(
+                "Can only compare against a GenomeAssembly or ChromSizes"
+            ));
+        };
+        Ok(comparison.into())
+    }
+}
+
+impl PyGenomeAssembly {
+    fn collection(&self) -> anyhow::Result<SequenceCollection> {
+        SequenceCollection::from_genome(&self.genome_assembly)
+    }
+}
+
+#[pyclass(name = "ChromSizes")]
+pub struct PyChromSizes {
+    pub chrom_sizes: ChromSizes,
+}
+
+#[pymethods]
+impl PyChromSizes {
+    #[new]
+    pub fn new(path: String) -> anyhow::Result<Self> {
         let path = Path::new(&path);
-        let genome_assembly = GenomeAssembly::from_fasta(path).unwrap();
-        PyGenomeAssembly { genome_assembly }
+        let chrom_sizes = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("fa") | Some("fasta") => ChromSizes::from_fasta(path)?,
+            _ => ChromSizes::from_file(path)?,
+        };
+        Ok(PyChromSizes { chrom_sizes })
+    }
+
+    #[staticmethod]
+    pub fn builtin(assembly: String) -> anyhow::Result<Self> {
+        let chrom_sizes = ChromSizes::builtin(&assembly)?;
+        Ok(PyChromSizes { chrom_sizes })
+    }
+
+    pub fn get(&self, chr: String) -> Option<u32> {
+        self.chrom_sizes.get(&chr)
+    }
+
+    pub fn validate(&self, file: String) -> anyhow::Result<()> {
+        let rs = RegionSet::from_bed(Path::new(&file))?;
+        self.chrom_sizes.validate(&rs)
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.chrom_sizes.len()
+    }
+
+    pub fn __contains__(&self, chr: String) -> bool {
+        self.chrom_sizes.contains_chr(&chr)
+    }
+}
+
+#[pyclass(name = "AssemblyGuess", get_all)]
+pub struct PyAssemblyGuess {
+    pub assembly: String,
+    pub naming: String,
+    pub confidence: f64,
+    pub n_unplaced: usize,
+    // (assembly, n_compatible, n_incompatible, probability)
+    pub candidates: Vec<(String, usize, usize, f64)>,
+    pub incompatible_regions: Vec<(String, u32, u32)>,
+}
+
+impl From<AssemblyGuess> for PyAssemblyGuess {
+    fn from(guess: AssemblyGuess) -> Self {
+        PyAssemblyGuess {
+            assembly: guess.assembly,
+            naming: guess.naming.to_string(),
+            confidence: guess.confidence,
+            n_unplaced: guess.n_unplaced,
+            candidates: guess
+                .candidates
+                .into_iter()
+                .map(|c| (c.assembly, c.n_compatible, c.n_incompatible, c.probability))
+                .collect(),
+            incompatible_regions: guess
+                .incompatible_regions
+                .into_iter()
+                .map(|r| (r.chr, r.start, r.end))
+                .collect(),
+        }
+    }
+}
+
+#[pyclass(name = "AssemblyComparison", get_all)]
+pub struct PyAssemblyComparison {
+    pub compatible: bool,
+    pub identical: bool,
+    pub shared: Vec<String>,
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+    pub length_mismatches: Vec<String>,
+    pub sequence_mismatches: Vec<String>,
+}
+
+impl From<AssemblyComparison> for PyAssemblyComparison {
+    fn from(comparison: AssemblyComparison) -> Self {
+        PyAssemblyComparison {
+            compatible: comparison.is_compatible(),
+            identical: comparison.identical,
+            shared: comparison.shared,
+            only_in_a: comparison.only_in_a,
+            only_in_b: comparison.only_in_b,
+            length_mismatches: comparison.length_mismatches,
+            sequence_mismatches: comparison.sequence_mismatches,
+        }
+    }
+}
+
+#[pyclass(name = "LengthSummary", get_all)]
+pub struct PyLengthSummary {
+    pub n: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub min: Option<u32>,
+    pub max: Option<u32>,
+}
+
+impl From<LengthSummary> for PyLengthSummary {
+    fn from(summary: LengthSummary) -> Self {
+        PyLengthSummary {
+            n: summary.n,
+            mean: summary.mean,
+            median: summary.median,
+            min: summary.min,
+            max: summary.max,
+        }
+    }
+}
+
+#[pyclass(name = "GcBackground", get_all)]
+pub struct PyGcBackground {
+    pub observed: Vec<f64>,
+    pub background: Vec<f64>,
+    pub observed_mean: f64,
+    pub background_mean: f64,
+    pub mean_difference: f64,
+    pub ks_statistic: f64,
+}
+
+impl From<GcBackground> for PyGcBackground {
+    fn from(gc_ref: GcBackground) -> Self {
+        PyGcBackground {
+            observed: gc_ref.observed,
+            background: gc_ref.background,
+            observed_mean: gc_ref.observed_mean,
+            background_mean: gc_ref.background_mean,
+            mean_difference: gc_ref.mean_difference,
+            ks_statistic: gc_ref.ks_statistic,
+        }
     }
 }
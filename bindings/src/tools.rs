@@ -1,26 +1,155 @@
 use pyo3::prelude::*;
+use pyo3::types::{PyByteArray, PyTuple};
 
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::path::Path;
 
-use crate::models::PyGenomeAssembly;
+use gdrs::digest::RestrictionEnzyme;
+use gdrs::models::{
+    ChromClass, CpgIslandCriteria, FastaName, FastaOptions, GcBackgroundMethod, GcContentOptions,
+    LengthSummary, MaskFilter, OneHotOptions, Region, RegionMatrix, RegionSet,
+};
+use gdrs::motifs::{Motif, MotifEnrichmentOptions, MotifFormat};
+use gdrs::patterns::Pattern;
+
+use crate::models::{PyAssemblyGuess, PyGcBackground, PyGenomeAssembly, PyLengthSummary};
+
+// chr, start, end, gc_content, n_fraction
+type RegionGcRow = (String, u32, u32, Option<f64>, Option<f64>);
 
 #[pyfunction(name = "calc_gc_content")]
+#[pyo3(signature = (file, genome, ignore_unk_chroms=None, exclude_n=false, mask="all"))]
 pub fn py_calc_gc_content(
     file: String,
     genome: &PyGenomeAssembly,
     ignore_unk_chroms: Option<bool>,
-) -> anyhow::Result<Vec<f64>> {
+    exclude_n: bool,
+    mask: &str,
+) -> anyhow::Result<Vec<Option<f64>>> {
     let path = Path::new(&file);
     let rs = gdrs::models::RegionSet::from_bed(path)?;
 
-    gdrs::calc_gc_content(
-        &rs,
-        &genome.genome_assembly,
-        ignore_unk_chroms.unwrap_or(false),
+    let options = GcContentOptions {
+        ignore_unk_chroms: ignore_unk_chroms.unwrap_or(false),
+        exclude_ambiguous: exclude_n,
+        mask: mask.parse::<MaskFilter>()?,
+    };
+
+    Ok(
+        gdrs::calc_gc_content_stats(&rs, &genome.genome_assembly, &options)?
+            .into_iter()
+            .map(|stats| stats.gc_content)
+            .collect(),
+    )
+}
+
+/// (chr, start, end, gc_content, n_fraction) of every region.
+#[pyfunction(name = "calc_gc_content_stats")]
+#[pyo3(signature = (file, genome, ignore_unk_chroms=None, exclude_n=false, mask="all"))]
+pub fn py_calc_gc_content_stats(
+    file: String,
+    genome: &PyGenomeAssembly,
+    ignore_unk_chroms: Option<bool>,
+    exclude_n: bool,
+    mask: &str,
+) -> anyhow::Result<Vec<RegionGcRow>> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    let options = GcContentOptions {
+        ignore_unk_chroms: ignore_unk_chroms.unwrap_or(false),
+        exclude_ambiguous: exclude_n,
+        mask: mask.parse::<MaskFilter>()?,
+    };
+
+    Ok(
+        gdrs::calc_gc_content_stats(&rs, &genome.genome_assembly, &options)?
+            .into_iter()
+            .map(|stats| {
+                (
+                    stats.region.chr,
+                    stats.region.start,
+                    stats.region.end,
+                    stats.gc_content,
+                    stats.n_fraction,
+                )
+            })
+            .collect(),
+    )
+}
+
+#[pyfunction(name = "calc_n_fraction")]
+#[pyo3(signature = (file, genome, ignore_unk_chroms=None))]
+pub fn py_calc_n_fraction(
+    file: String,
+    genome: &PyGenomeAssembly,
+    ignore_unk_chroms: Option<bool>,
+) -> anyhow::Result<Vec<Option<f64>>> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    let options = GcContentOptions {
+        ignore_unk_chroms: ignore_unk_chroms.unwrap_or(false),
+        ..Default::default()
+    };
+
+    Ok(
+        gdrs::calc_gc_content_stats(&rs, &genome.genome_assembly, &options)?
+            .into_iter()
+            .map(|stats| stats.n_fraction)
+            .collect(),
     )
 }
 
+/// Compare the gc content of the regions to a genome-wide background.
+/// "tile" tiles the genome at the median query width, an approximation of
+/// the query widths; "sample" draws windows with each query's width.
+#[pyfunction(name = "calc_gc_content_ref")]
+#[pyo3(signature = (file, genome, method="tile", n_samples=10000, seed=42, ignore_unk_chroms=false, exclude_n=false, mask="all"))]
+#[allow(clippy::too_many_arguments)]
+pub fn py_calc_gc_content_ref(
+    file: String,
+    genome: &PyGenomeAssembly,
+    method: &str,
+    n_samples: usize,
+    seed: u64,
+    ignore_unk_chroms: bool,
+    exclude_n: bool,
+    mask: &str,
+) -> anyhow::Result<PyGcBackground> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    let method = match method {
+        "tile" => GcBackgroundMethod::Tile,
+        "sample" => GcBackgroundMethod::Sample { n_samples, seed },
+        _ => anyhow::bail!(
+            "Unknown background method '{}', use 'tile' or 'sample'",
+            method
+        ),
+    };
+    let options = GcContentOptions {
+        ignore_unk_chroms,
+        exclude_ambiguous: exclude_n,
+        mask: mask.parse::<MaskFilter>()?,
+    };
+
+    Ok(gdrs::calc_gc_content_ref(&rs, &genome.genome_assembly, method, &options)?.into())
+}
+
+#[pyfunction(name = "calc_repeat_fraction")]
+pub fn py_calc_repeat_fraction(
+    file: String,
+    genome: &PyGenomeAssembly,
+) -> anyhow::Result<Vec<Option<f64>>> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    gdrs::calc_repeat_fraction(&rs, &genome.genome_assembly)
+}
+
 #[pyfunction(name = "calc_neighbor_distances")]
 pub fn py_calc_neighbor_distances(file: String) -> anyhow::Result<Vec<u32>> {
     let path = Path::new(&file);
@@ -32,14 +161,22 @@ pub fn py_calc_neighbor_distances(file: String) -> anyhow::Result<Vec<u32>> {
 }
 
 #[pyfunction(name = "calc_dincleotide_frequency")]
+#[pyo3(signature = (file, genome, raw_counts=false))]
 pub fn py_calc_dinucleotide_frequency(
     file: String,
     genome: &PyGenomeAssembly,
+    raw_counts: bool,
 ) -> anyhow::Result<HashMap<String, f64>> {
     let path = Path::new(&file);
     let rs = gdrs::models::RegionSet::from_bed(path)?;
 
-    let frequencies = gdrs::calc_dinucl_freq(&rs, &genome.genome_assembly)?;
+    let frequencies = match raw_counts {
+        true => gdrs::calc_dinucl_counts(&rs, &genome.genome_assembly)?
+            .into_iter()
+            .map(|(di, count)| (di, count as f64))
+            .collect(),
+        false => gdrs::calc_dinucl_freq(&rs, &genome.genome_assembly)?,
+    };
 
     let mut freq_map: HashMap<String, f64> = HashMap::new();
 
@@ -51,6 +188,183 @@ pub fn py_calc_dinucleotide_frequency(
     Ok(freq_map)
 }
 
+// chr, start, end, cpg_count, cpg_density, cpg_oe
+type CpgRow = (String, u32, u32, u32, f64, f64);
+
+#[pyfunction(name = "calc_cpg_oe")]
+pub fn py_calc_cpg_oe(file: String, genome: &PyGenomeAssembly) -> anyhow::Result<Vec<CpgRow>> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    let cpg_stats = gdrs::calc_cpg_oe(&rs, &genome.genome_assembly)?;
+
+    Ok(cpg_stats
+        .into_iter()
+        .map(|stats| {
+            (
+                stats.region.chr,
+                stats.region.start,
+                stats.region.end,
+                stats.cpg_count,
+                stats.cpg_density,
+                stats.cpg_oe,
+            )
+        })
+        .collect())
+}
+
+// chr, start, end, entropy, dust_score, longest_homopolymer, dinucleotide_repeat_fraction
+type ComplexityRow = (String, u32, u32, f64, f64, u32, f64);
+
+#[pyfunction(name = "calc_complexity")]
+#[pyo3(signature = (file, genome, k=2))]
+pub fn py_calc_complexity(
+    file: String,
+    genome: &PyGenomeAssembly,
+    k: usize,
+) -> anyhow::Result<Vec<ComplexityRow>> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    let complexity_stats = gdrs::calc_complexity(&rs, &genome.genome_assembly, k)?;
+
+    Ok(complexity_stats
+        .into_iter()
+        .map(|stats| {
+            (
+                stats.region.chr,
+                stats.region.start,
+                stats.region.end,
+                stats.entropy,
+                stats.dust_score,
+                stats.longest_homopolymer,
+                stats.dinucleotide_repeat_fraction,
+            )
+        })
+        .collect())
+}
+
+#[pyfunction(name = "find_cpg_islands")]
+#[pyo3(signature = (genome, file=None, criteria="gardiner-garden", min_length=None, min_gc=None, min_oe=None))]
+pub fn py_find_cpg_islands(
+    genome: &PyGenomeAssembly,
+    file: Option<String>,
+    criteria: &str,
+    min_length: Option<u32>,
+    min_gc: Option<f64>,
+    min_oe: Option<f64>,
+) -> anyhow::Result<Vec<(String, u32, u32)>> {
+    let rs = match file {
+        Some(file) => Some(gdrs::models::RegionSet::from_bed(Path::new(&file))?),
+        None => None,
+    };
+
+    let mut criteria = criteria.parse::<CpgIslandCriteria>()?;
+    criteria.min_length = min_length.unwrap_or(criteria.min_length);
+    criteria.min_gc = min_gc.unwrap_or(criteria.min_gc);
+    criteria.min_oe = min_oe.unwrap_or(criteria.min_oe);
+
+    let islands = gdrs::find_cpg_islands(&genome.genome_assembly, rs.as_ref(), &criteria)?;
+
+    Ok(islands
+        .iter()
+        .map(|r| (r.chr.to_string(), r.start, r.end))
+        .collect())
+}
+
+#[pyfunction(name = "find_gaps")]
+#[pyo3(signature = (genome, min_length=1, output=None))]
+pub fn py_find_gaps(
+    genome: &PyGenomeAssembly,
+    min_length: u32,
+    output: Option<String>,
+) -> anyhow::Result<Vec<(String, u32, u32)>> {
+    let gaps = gdrs::find_gaps(&genome.genome_assembly, min_length)?;
+
+    if let Some(output) = output {
+        let mut file = BufWriter::new(File::create(&output)?);
+        gaps.write_bed(&mut file)?;
+        file.flush()?;
+    }
+
+    Ok(gaps
+        .iter()
+        .map(|r| (r.chr.to_string(), r.start, r.end))
+        .collect())
+}
+
+/// Cut the genome into restriction fragments, only on chromosomes of the
+/// classes in `keep` (e.g. ["autosome", "sex"]), all of them by default.
+#[pyfunction(name = "digest_genome")]
+#[pyo3(signature = (genome, enzymes, output=None, keep=None))]
+pub fn py_digest_genome(
+    genome: &PyGenomeAssembly,
+    enzymes: Vec<String>,
+    output: Option<String>,
+    keep: Option<Vec<String>>,
+) -> anyhow::Result<Vec<(String, u32, u32)>> {
+    let enzymes = enzymes
+        .iter()
+        .map(|spec| RestrictionEnzyme::from_name_or_site(spec))
+        .collect::<anyhow::Result<Vec<RestrictionEnzyme>>>()?;
+
+    let keep = match keep {
+        Some(classes) => classes
+            .iter()
+            .map(|class| class.parse::<ChromClass>())
+            .collect::<anyhow::Result<Vec<ChromClass>>>()?,
+        None => ChromClass::ALL.to_vec(),
+    };
+
+    let fragments = gdrs::digest_genome(&genome.genome_assembly, &enzymes, &keep)?;
+
+    if let Some(output) = output {
+        let mut file = BufWriter::new(File::create(&output)?);
+        fragments.write_bed(&mut file)?;
+        file.flush()?;
+    }
+
+    Ok(fragments
+        .iter()
+        .map(|r| (r.chr.to_string(), r.start, r.end))
+        .collect())
+}
+
+// fragments as returned by `digest_genome`, or the path of a fragments bed file
+fn fragments_from_py(fragments: &Bound<'_, PyAny>) -> anyhow::Result<RegionSet> {
+    if let Ok(path) = fragments.extract::<String>() {
+        return RegionSet::from_bed(Path::new(&path));
+    }
+    let fragments: Vec<(String, u32, u32)> = fragments.extract().map_err(|_| {
+        anyhow::anyhow!("Fragments must be a bed file path or a list of (chr, start, end) tuples")
+    })?;
+    Ok(RegionSet::from_regions(
+        fragments
+            .iter()
+            .map(|(chr, start, end)| Region::new(chr, *start, *end))
+            .collect(),
+    ))
+}
+
+#[pyfunction(name = "map_to_fragments")]
+pub fn py_map_to_fragments(
+    file: String,
+    fragments: &Bound<'_, PyAny>,
+) -> anyhow::Result<Vec<Vec<usize>>> {
+    let rs = gdrs::models::RegionSet::from_bed(Path::new(&file))?;
+    let fragments = fragments_from_py(fragments)?;
+
+    Ok(gdrs::map_to_fragments(&rs, &fragments))
+}
+
+#[pyfunction(name = "fragment_length_summary")]
+pub fn py_fragment_length_summary(fragments: &Bound<'_, PyAny>) -> anyhow::Result<PyLengthSummary> {
+    let fragments = fragments_from_py(fragments)?;
+    let lengths: Vec<u32> = fragments.iter().map(|fragment| fragment.width()).collect();
+
+    Ok(LengthSummary::from_lengths(&lengths).into())
+}
+
 #[pyfunction(name = "calc_widths")]
 pub fn py_calc_widths(file: String) -> anyhow::Result<Vec<u32>> {
     let path = Path::new(&file);
@@ -58,3 +372,344 @@ pub fn py_calc_widths(file: String) -> anyhow::Result<Vec<u32>> {
 
     gdrs::calc_widths(&rs)
 }
+
+#[pyfunction(name = "region_set_digest")]
+#[pyo3(signature = (file, include_names=false))]
+pub fn py_region_set_digest(file: String, include_names: bool) -> anyhow::Result<String> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    Ok(rs.digest(include_names))
+}
+
+#[pyfunction(name = "detect_assembly")]
+pub fn py_detect_assembly(file: String) -> anyhow::Result<PyAssemblyGuess> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    Ok(gdrs::detect_assembly(&rs)?.into())
+}
+
+#[pyfunction(name = "calc_dinucleotide_frequency_per_region")]
+#[pyo3(signature = (file, genome, raw_counts=false))]
+pub fn py_calc_dinucleotide_frequency_per_region(
+    py: Python<'_>,
+    file: String,
+    genome: &PyGenomeAssembly,
+    raw_counts: bool,
+) -> anyhow::Result<PyObject> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    let matrix = gdrs::calc_dinucl_freq_per_region(&rs, &genome.genome_assembly, raw_counts)?;
+
+    Ok(region_matrix_to_py(py, &matrix)?)
+}
+
+#[pyfunction(name = "calc_kmer_frequency")]
+#[pyo3(signature = (file, genome, k, fold_revcomp=false, raw_counts=false, mask="all"))]
+pub fn py_calc_kmer_frequency(
+    file: String,
+    genome: &PyGenomeAssembly,
+    k: usize,
+    fold_revcomp: bool,
+    raw_counts: bool,
+    mask: &str,
+) -> anyhow::Result<HashMap<String, f64>> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    let kmer_counts = gdrs::calc_kmer_counts(
+        &rs,
+        &genome.genome_assembly,
+        k,
+        fold_revcomp,
+        mask.parse::<MaskFilter>()?,
+    )?;
+    let total = kmer_counts.total() as f64;
+
+    let freq_map = kmer_counts
+        .iter()
+        .map(|(kmer, count)| match raw_counts {
+            true => (kmer, count as f64),
+            false => (kmer, count as f64 / total * 100.0),
+        })
+        .collect();
+
+    Ok(freq_map)
+}
+
+#[pyfunction(name = "calc_kmer_frequency_per_region")]
+#[pyo3(signature = (file, genome, k, fold_revcomp=false, raw_counts=false, mask="all"))]
+pub fn py_calc_kmer_frequency_per_region(
+    py: Python<'_>,
+    file: String,
+    genome: &PyGenomeAssembly,
+    k: usize,
+    fold_revcomp: bool,
+    raw_counts: bool,
+    mask: &str,
+) -> anyhow::Result<PyObject> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    let matrix = gdrs::calc_kmer_freq_per_region(
+        &rs,
+        &genome.genome_assembly,
+        k,
+        fold_revcomp,
+        mask.parse::<MaskFilter>()?,
+        raw_counts,
+    )?;
+
+    Ok(region_matrix_to_py(py, &matrix)?)
+}
+
+fn fasta_options(use_names: bool, stranded: bool, resize: Option<u32>) -> FastaOptions {
+    FastaOptions {
+        name_by: match use_names {
+            true => FastaName::Name,
+            false => FastaName::Coords,
+        },
+        stranded,
+        resize,
+    }
+}
+
+#[pyfunction(name = "extract_sequences")]
+#[pyo3(signature = (file, genome, use_names=false, stranded=false, resize=None))]
+pub fn py_extract_sequences(
+    file: String,
+    genome: &PyGenomeAssembly,
+    use_names: bool,
+    stranded: bool,
+    resize: Option<u32>,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    let options = fasta_options(use_names, stranded, resize);
+    let records = gdrs::extract_sequences(&rs, &genome.genome_assembly, &options)?;
+
+    Ok(records
+        .into_iter()
+        .map(|(name, seq)| (name, String::from_utf8_lossy(&seq).into_owned()))
+        .collect())
+}
+
+#[pyfunction(name = "one_hot_encode")]
+#[pyo3(signature = (file, genome, resize=None, stranded=false, reverse_complement=false))]
+pub fn py_one_hot_encode(
+    py: Python<'_>,
+    file: String,
+    genome: &PyGenomeAssembly,
+    resize: Option<u32>,
+    stranded: bool,
+    reverse_complement: bool,
+) -> anyhow::Result<PyObject> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    let options = OneHotOptions {
+        resize,
+        stranded,
+        reverse_complement,
+    };
+    let one_hot = gdrs::one_hot_encode(&rs, &genome.genome_assembly, &options)?;
+
+    // a bytearray keeps the array writable without copying through python ints
+    let numpy = py.import_bound("numpy")?;
+    let buffer = PyByteArray::new_bound(py, &one_hot.values);
+    let array = numpy
+        .call_method1("frombuffer", (buffer, "uint8"))?
+        .call_method1("reshape", ((one_hot.n_seqs, one_hot.width, 4),))?;
+    Ok(array.into_py(py))
+}
+
+#[pyfunction(name = "write_fasta")]
+#[pyo3(signature = (file, genome, output, use_names=false, stranded=false, resize=None))]
+pub fn py_write_fasta(
+    file: String,
+    genome: &PyGenomeAssembly,
+    output: String,
+    use_names: bool,
+    stranded: bool,
+    resize: Option<u32>,
+) -> anyhow::Result<()> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    let options = fasta_options(use_names, stranded, resize);
+    let mut writer = BufWriter::new(File::create(output)?);
+    gdrs::write_fasta(&rs, &genome.genome_assembly, &options, &mut writer)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+// chr, start, end, motif, strand, score, p_value
+type MotifHitRow = (String, u32, u32, String, String, f64, f64);
+
+#[pyfunction(name = "scan_motifs")]
+#[pyo3(signature = (file, genome, motifs, p_value=1e-4, format=None))]
+pub fn py_scan_motifs(
+    py: Python<'_>,
+    file: String,
+    genome: &PyGenomeAssembly,
+    motifs: String,
+    p_value: f64,
+    format: Option<String>,
+) -> anyhow::Result<(PyObject, Vec<MotifHitRow>)> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    let format = format
+        .map(|format| format.parse::<MotifFormat>())
+        .transpose()?;
+    let motifs = Motif::from_file(Path::new(&motifs), format)?;
+
+    let scan = gdrs::scan_motifs(&rs, &genome.genome_assembly, &motifs, p_value)?;
+
+    let hits = scan
+        .hits
+        .into_iter()
+        .map(|hit| {
+            (
+                hit.chr,
+                hit.start,
+                hit.end,
+                scan.motifs[hit.motif_index].to_string(),
+                hit.strand.to_string(),
+                hit.score,
+                hit.p_value,
+            )
+        })
+        .collect();
+
+    Ok((region_matrix_to_py(py, &scan.counts)?, hits))
+}
+
+// motif, query_hits, query_total, background_hits, background_total, fold_enrichment, p_value, q_value
+type MotifEnrichmentRow = (String, usize, usize, usize, usize, f64, f64, f64);
+
+#[pyfunction(name = "calc_motif_enrichment")]
+#[pyo3(signature = (file, genome, motifs, p_value=1e-4, background_ratio=2, gc_bin_width=0.05, seed=42, format=None))]
+#[allow(clippy::too_many_arguments)]
+pub fn py_calc_motif_enrichment(
+    file: String,
+    genome: &PyGenomeAssembly,
+    motifs: String,
+    p_value: f64,
+    background_ratio: usize,
+    gc_bin_width: f64,
+    seed: u64,
+    format: Option<String>,
+) -> anyhow::Result<Vec<MotifEnrichmentRow>> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    let format = format
+        .map(|format| format.parse::<MotifFormat>())
+        .transpose()?;
+    let motifs = Motif::from_file(Path::new(&motifs), format)?;
+
+    let options = MotifEnrichmentOptions {
+        p_value,
+        background_ratio,
+        gc_bin_width,
+        seed,
+    };
+    let enrichment = gdrs::calc_motif_enrichment(&rs, &genome.genome_assembly, &motifs, &options)?;
+
+    Ok(enrichment
+        .into_iter()
+        .map(|motif| {
+            (
+                motif.motif,
+                motif.query_hits,
+                motif.query_total,
+                motif.background_hits,
+                motif.background_total,
+                motif.fold_enrichment,
+                motif.p_value,
+                motif.q_value,
+            )
+        })
+        .collect())
+}
+
+// chr, start, end, pattern, strand
+type PatternHitRow = (String, u32, u32, String, String);
+
+#[pyfunction(name = "search_patterns")]
+#[pyo3(signature = (file, genome, patterns, both_strands=true))]
+pub fn py_search_patterns(
+    py: Python<'_>,
+    file: String,
+    genome: &PyGenomeAssembly,
+    patterns: Vec<String>,
+    both_strands: bool,
+) -> anyhow::Result<(PyObject, Vec<PatternHitRow>)> {
+    let path = Path::new(&file);
+    let rs = gdrs::models::RegionSet::from_bed(path)?;
+
+    let patterns = patterns
+        .iter()
+        .map(|pattern| Pattern::new(pattern))
+        .collect::<anyhow::Result<Vec<Pattern>>>()?;
+
+    let scan = gdrs::search_patterns(&rs, &genome.genome_assembly, &patterns, both_strands)?;
+
+    let hits = scan
+        .hits
+        .into_iter()
+        .map(|hit| {
+            (
+                hit.chr,
+                hit.start,
+                hit.end,
+                scan.patterns[hit.pattern_index].to_string(),
+                hit.strand.to_string(),
+            )
+        })
+        .collect();
+
+    Ok((region_matrix_to_py(py, &scan.counts)?, hits))
+}
+
+// (regions, columns, values) with values as a (n_regions, n_columns) numpy array
+fn region_matrix_to_py(py: Python<'_>, matrix: &RegionMatrix) -> PyResult<PyObject> {
+    let values = to_numpy_2d(py, matrix.values(), matrix.n_rows(), matrix.n_cols())?;
+    let regions: Vec<(String, u32, u32)> = matrix
+        .regions
+        .iter()
+        .map(|r| (r.chr.to_string(), r.start, r.end))
+        .collect();
+
+    let result = PyTuple::new_bound(
+        py,
+        [
+            regions.into_py(py),
+            matrix.columns.clone().into_py(py),
+            values,
+        ],
+    );
+    Ok(result.into_py(py))
+}
+
+fn to_numpy_2d(py: Python<'_>, values: &[f64], n_rows: usize, n_cols: usize) -> PyResult<PyObject> {
+    // copy the native-endian bytes straight into a bytearray, like one_hot_encode
+    let width = std::mem::size_of::<f64>();
+    let buffer = PyByteArray::new_bound_with(py, std::mem::size_of_val(values), |bytes| {
+        for (chunk, value) in bytes.chunks_exact_mut(width).zip(values) {
+            chunk.copy_from_slice(&value.to_ne_bytes());
+        }
+        Ok(())
+    })?;
+
+    let numpy = py.import_bound("numpy")?;
+    let array = numpy
+        .call_method1("frombuffer", (buffer, "float64"))?
+        .call_method1("reshape", ((n_rows, n_cols),))?;
+    Ok(array.into_py(py))
+}
@@ -0,0 +1,122 @@
+use anyhow::{ensure, Result};
+
+use crate::models::reverse_complement;
+use crate::patterns::{iupac_bases, Pattern};
+
+// name, recognition site with the top strand cut marked by '^'
+const BUILTIN_ENZYMES: [(&str, &str); 19] = [
+    ("AluI", "AG^CT"),
+    ("BamHI", "G^GATCC"),
+    ("BglII", "A^GATCT"),
+    ("Csp6I", "G^TAC"),
+    ("CviQI", "G^TAC"),
+    ("DdeI", "C^TNAG"),
+    ("DpnII", "^GATC"),
+    ("EcoRI", "G^AATTC"),
+    ("HindIII", "A^AGCTT"),
+    ("HinfI", "G^ANTC"),
+    ("MboI", "^GATC"),
+    ("MluCI", "^AATT"),
+    ("MseI", "T^TAA"),
+    ("NcoI", "C^CATGG"),
+    ("NlaIII", "CATG^"),
+    ("NotI", "GC^GGCCGC"),
+    ("Sau3AI", "^GATC"),
+    ("XbaI", "T^CTAGA"),
+    ("XhoI", "C^TCGAG"),
+];
+
+/// A restriction enzyme: an IUPAC recognition site and the offset into the
+/// site at which it cuts the top strand.
+#[derive(Debug, Clone)]
+pub struct RestrictionEnzyme {
+    pub name: String,
+    site: String,
+    cut: usize,
+}
+
+impl RestrictionEnzyme {
+    /// Build an enzyme from a site such as `A^AGCTT`, where `^` marks the cut.
+    /// Sites without a `^` are cut at their start.
+    pub fn new(name: &str, site: &str) -> Result<RestrictionEnzyme> {
+        let site = site.trim().to_ascii_uppercase();
+        let cut = site.find('^').unwrap_or(0);
+        let bases = site.replace('^', "");
+
+        ensure!(!bases.is_empty(), "Recognition site of {} is empty", name);
+        ensure!(
+            site.matches('^').count() <= 1,
+            "Recognition site '{}' of {} marks more than one cut",
+            site,
+            name
+        );
+        ensure!(
+            bases.bytes().all(|base| iupac_bases(base).is_some()),
+            "Recognition site '{}' of {} may only contain IUPAC codes and one '^'",
+            site,
+            name
+        );
+
+        Ok(RestrictionEnzyme {
+            name: name.to_string(),
+            site: bases,
+            cut,
+        })
+    }
+
+    /// A built-in enzyme by name, case-insensitive, such as `DpnII` or `HindIII`,
+    /// or otherwise a recognition site.
+    pub fn from_name_or_site(spec: &str) -> Result<RestrictionEnzyme> {
+        match BUILTIN_ENZYMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(spec.trim()))
+        {
+            Some((name, site)) => RestrictionEnzyme::new(name, site),
+            None => RestrictionEnzyme::new(spec.trim(), spec).map_err(|e| {
+                anyhow::anyhow!(
+                    "{}. Known enzymes: {}",
+                    e,
+                    BUILTIN_ENZYMES
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                )
+            }),
+        }
+    }
+
+    pub fn site(&self) -> &str {
+        &self.site
+    }
+
+    pub fn cut_offset(&self) -> usize {
+        self.cut
+    }
+
+    /// Every top strand cut position in `seq`, sorted and without duplicates.
+    /// Sites that are not palindromic are also searched on the bottom strand.
+    pub fn cut_positions(&self, seq: &[u8]) -> Result<Vec<usize>> {
+        let site_len = self.site.len();
+
+        let mut cuts: Vec<usize> = Pattern::new(&self.site)?
+            .find_all(seq)
+            .into_iter()
+            .map(|(start, _)| start + self.cut)
+            .collect();
+
+        let revcomp = String::from_utf8(reverse_complement(self.site.as_bytes()))?;
+        if revcomp != self.site {
+            cuts.extend(
+                Pattern::new(&revcomp)?
+                    .find_all(seq)
+                    .into_iter()
+                    .map(|(start, _)| start + site_len - self.cut),
+            );
+        }
+
+        cuts.sort_unstable();
+        cuts.dedup();
+        Ok(cuts)
+    }
+}
@@ -1,10 +1,102 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
 
 use anyhow::{ensure, Result};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
+pub mod digest;
+pub mod mapped;
 pub mod models;
+pub mod motifs;
+pub mod patterns;
+pub mod refget;
+pub mod twobit;
 
-use models::{Dinucleotide, GenomeAssembly, RegionSet, TSSIndex};
+use digest::RestrictionEnzyme;
+use models::{
+    encode_kmer, reverse_complement, AssemblyCandidate, AssemblyGuess, ChromAliases, ChromClass,
+    ChromNaming, ChromSizes, ComplexityStats, CpgIslandCriteria, CpgStats, Dinucleotide, FastaName,
+    FastaOptions, GcBackground, GcBackgroundMethod, GcContentOptions, GcStats, GenomeAssembly,
+    GenomeFormat, KmerCounts, MaskFilter, OneHotOptions, OneHotSequences, Region, RegionMatrix,
+    RegionSet, Strand, TSSIndex, BUILTIN_ASSEMBLIES,
+};
+use motifs::{Motif, MotifEnrichment, MotifEnrichmentOptions, MotifHit, MotifScan, Pwm};
+use patterns::{Pattern, PatternHit, PatternScan};
+use rust_lapper::{Interval, Lapper};
+
+// window size of the symmetric DUST score
+const DUST_WINDOW: usize = 64;
+
+// shortest dinucleotide repeat counted by calc_complexity, in repeat units
+const DINUCLEOTIDE_REPEAT_MIN_UNITS: usize = 4;
+
+// random windows drawn per background region before giving up on a GC bin
+const BACKGROUND_ATTEMPTS: usize = 1000;
+
+// the probability that any single region is junk, used when scoring assemblies
+const ASSEMBLY_ERROR_RATE: f64 = 0.01;
+
+// returns the number of G/C bases, N/ambiguous bases and all bases that pass the mask
+fn count_gc(seq: &[u8], mask: MaskFilter) -> (u32, u32, u32) {
+    let mut gc_count: u32 = 0;
+    let mut n_count: u32 = 0;
+    let mut total_count: u32 = 0;
+    for base in seq.iter().filter(|base| mask.accepts(**base)) {
+        match base.to_ascii_lowercase() {
+            b'g' | b'c' => gc_count += 1,
+            b'a' | b't' => {}
+            // N and the IUPAC ambiguity codes
+            _ => n_count += 1,
+        }
+        total_count += 1;
+    }
+    (gc_count, n_count, total_count)
+}
+
+fn gc_fraction(seq: &[u8], options: &GcContentOptions) -> Option<f64> {
+    let (gc_count, n_count, total_count) = count_gc(seq, options.mask);
+    let informative_count = match options.exclude_ambiguous {
+        true => total_count - n_count,
+        false => total_count,
+    };
+    (informative_count > 0).then(|| gc_count as f64 / informative_count as f64)
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+// largest distance between the empirical CDFs of two samples
+fn ks_statistic(a: &[f64], b: &[f64]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return f64::NAN;
+    }
+
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort_by(|x, y| x.total_cmp(y));
+    b.sort_by(|x, y| x.total_cmp(y));
+
+    let (mut i, mut j) = (0, 0);
+    let mut statistic: f64 = 0.0;
+    while i < a.len() && j < b.len() {
+        let x = a[i].min(b[j]);
+        while i < a.len() && a[i] <= x {
+            i += 1;
+        }
+        while j < b.len() && b[j] <= x {
+            j += 1;
+        }
+        let diff = (i as f64 / a.len() as f64 - j as f64 / b.len() as f64).abs();
+        statistic = statistic.max(diff);
+    }
+
+    statistic
+}
 
 pub fn calc_neighbor_distances(region_set: &RegionSet) -> Result<Vec<u32>> {
     // make sure that the regions are sorted
@@ -37,52 +129,184 @@ pub fn calc_gc_content(
     genome: &GenomeAssembly,
     ignore_unk_chroms: bool,
 ) -> Result<Vec<f64>> {
-    // for region in region_set
-    let mut gc_contents: Vec<f64> = vec![];
-    for chr in region_set.iter_chroms() {
+    let options = GcContentOptions {
+        ignore_unk_chroms,
+        ..Default::default()
+    };
 
+    Ok(calc_gc_content_stats(region_set, genome, &options)?
+        .into_iter()
+        .map(|stats| stats.gc_content.unwrap_or(f64::NAN))
+        .collect())
+}
+
+/// GC content and N fraction for each region, in input order. Either value
+/// is `None` when the region has no bases to divide by.
+pub fn calc_gc_content_stats(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+    options: &GcContentOptions,
+) -> Result<Vec<GcStats>> {
+    let mut gc_stats: Vec<GcStats> = vec![];
+    for region in region_set.iter() {
         // check if the chrom is even in genome
-        if ignore_unk_chroms && !genome.contains_chr(chr) {
+        if options.ignore_unk_chroms && !genome.contains_chr(&region.chr) {
             continue;
         }
 
-        for region in region_set.iter_regions(chr) {
+        let seq = match genome.seq_from_region(region) {
+            Ok(seq) => seq,
+            Err(e) => {
+                if options.ignore_unk_chroms {
+                    continue;
+                }
+                return Err(anyhow::anyhow!(
+                    "Error getting sequence for region {}:{}-{}: {}",
+                    region.chr,
+                    region.start,
+                    region.end,
+                    e
+                ));
+            }
+        };
+
+        let (_, n_count, total_count) = count_gc(seq, MaskFilter::All);
+
+        gc_stats.push(GcStats {
+            region: region.clone(),
+            gc_content: gc_fraction(seq, options),
+            n_fraction: (total_count > 0).then(|| n_count as f64 / total_count as f64),
+        });
+    }
+
+    Ok(gc_stats)
+}
+
+// draws windows uniformly from the genome, chromosomes weighted by length
+struct WindowSampler {
+    chroms: Vec<(String, u32)>,
+    weights: WeightedIndex<u64>,
+    rng: StdRng,
+}
+
+impl WindowSampler {
+    fn new(genome: &GenomeAssembly, seed: u64) -> Result<WindowSampler> {
+        // sort chromosomes by name so sampling is reproducible
+        let mut chroms: Vec<(String, u32)> = genome
+            .chrom_sizes()
+            .iter()
+            .map(|(chr, size)| (chr.to_string(), *size))
+            .collect();
+        chroms.sort();
+
+        let weights = WeightedIndex::new(chroms.iter().map(|(_, size)| *size as u64))
+            .map_err(|e| anyhow::anyhow!("Genome has no sequence to sample from: {}", e))?;
 
-            let mut gc_count: u32 = 0;
-            let mut total_count: u32 = 0;
-            let seq = genome.seq_from_region(region);
-
-            match seq {
-                Ok(seq) => {
-                    for base in seq {
-                        match base.to_ascii_lowercase() {
-                            b'g' | b'c' => {
-                                gc_count += 1;
-                            }
-                            _ => {}
-                        }
-                        total_count += 1;
+        Ok(WindowSampler {
+            chroms,
+            weights,
+            rng: StdRng::seed_from_u64(seed),
+        })
+    }
+
+    // None when the drawn chromosome is shorter than the window
+    fn sample(&mut self, width: u32) -> Option<Region> {
+        let (chr, size) = &self.chroms[self.weights.sample(&mut self.rng)];
+        if width > *size {
+            return None;
+        }
+
+        let start = self.rng.gen_range(0..=(*size - width));
+        Some(Region::new(chr, start, start + width))
+    }
+}
+
+/// Expected GC content of the genome, built from windows matched to the
+/// query widths, compared against the observed GC content of the regions.
+/// Tiling uses a single window width, the median query width, so it only
+/// approximates the width distribution; sampling matches it exactly.
+/// Windows without informative bases are left out of the background.
+pub fn calc_gc_content_ref(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+    method: GcBackgroundMethod,
+    options: &GcContentOptions,
+) -> Result<GcBackground> {
+    let observed: Vec<f64> = calc_gc_content_stats(region_set, genome, options)?
+        .into_iter()
+        .filter_map(|stats| stats.gc_content)
+        .collect();
+
+    let widths: Vec<u32> = calc_widths(region_set)?
+        .into_iter()
+        .filter(|width| *width > 0)
+        .collect();
+    ensure!(
+        !widths.is_empty(),
+        "RegionSet must contain at least one region with a non-zero width"
+    );
+
+    let mut background: Vec<f64> = vec![];
+    match method {
+        GcBackgroundMethod::Tile => {
+            // tile chromosomes in name order
+            let chrom_sizes = genome.chrom_sizes();
+            let mut chroms: Vec<(&String, &u32)> = chrom_sizes.iter().collect();
+            chroms.sort();
+
+            let mut sorted_widths = widths.clone();
+            sorted_widths.sort_unstable();
+            let width = sorted_widths[sorted_widths.len() / 2];
+
+            for (chr, size) in chroms {
+                let mut start: u32 = 0;
+                while start + width <= *size {
+                    let region = Region::new(chr, start, start + width);
+                    let seq = genome.seq_from_region(&region)?;
+                    if let Some(gc) = gc_fraction(seq, options) {
+                        background.push(gc);
                     }
-                    gc_contents.push(gc_count as f64 / total_count as f64);
+                    start += width;
                 }
-                Err(e) => {
-                    if ignore_unk_chroms {
-                        continue;
-                    } else {
-                        return Err(anyhow::anyhow!(
-                            "Error getting sequence for region {}:{}-{}: {}",
-                            region.chr.to_string(),
-                            region.start,
-                            region.end,
-                            e
-                        ));
-                    }
+            }
+        }
+        GcBackgroundMethod::Sample { n_samples, seed } => {
+            let mut sampler = WindowSampler::new(genome, seed)?;
+
+            // give up eventually if the genome is mostly gaps or too short
+            let max_attempts = n_samples.saturating_mul(10);
+            let mut attempts: usize = 0;
+            while background.len() < n_samples && attempts < max_attempts {
+                attempts += 1;
+
+                let width = widths[sampler.rng.gen_range(0..widths.len())];
+                let Some(region) = sampler.sample(width) else {
+                    continue;
+                };
+                let seq = genome.seq_from_region(&region)?;
+                if let Some(gc) = gc_fraction(seq, options) {
+                    background.push(gc);
                 }
             }
         }
     }
 
-    Ok(gc_contents)
+    ensure!(
+        !background.is_empty(),
+        "No background windows with informative bases could be drawn from the genome"
+    );
+
+    let observed_mean = mean(&observed);
+    let background_mean = mean(&background);
+
+    Ok(GcBackground {
+        observed_mean,
+        background_mean,
+        mean_difference: observed_mean - background_mean,
+        ks_statistic: ks_statistic(&observed, &background),
+        observed,
+        background,
+    })
 }
 
 pub fn calc_widths(region_set: &RegionSet) -> Result<Vec<u32>> {
@@ -95,31 +319,1044 @@ pub fn calc_widths(region_set: &RegionSet) -> Result<Vec<u32>> {
     Ok(widths)
 }
 
-pub fn calc_dinucl_freq(
+pub fn calc_dinucl_counts(
     region_set: &RegionSet,
     genome: &GenomeAssembly,
-) -> Result<HashMap<Dinucleotide, f64>> {
-    let mut dinucl_freqs: HashMap<Dinucleotide, f64> = HashMap::new();
+) -> Result<HashMap<Dinucleotide, u64>> {
+    let mut dinucl_counts: HashMap<Dinucleotide, u64> = HashMap::new();
 
     for chr in region_set.iter_chroms() {
         for region in region_set.iter_regions(chr) {
             let seq = genome.seq_from_region(region)?;
             for aas in seq.windows(2) {
-                let diucl = Dinucleotide::from_bytes(aas);
-                match diucl {
-                    Some(dinucl) => {
-                        let current_freq = dinucl_freqs.entry(dinucl).or_insert(0.0);
-                        *current_freq += 1.0;
-                    }
-                    None => continue,
+                // windows touching N or other ambiguity codes are skipped
+                if let Some(dinucl) = Dinucleotide::from_bytes(aas) {
+                    *dinucl_counts.entry(dinucl).or_insert(0) += 1;
                 }
             }
         }
     }
 
+    Ok(dinucl_counts)
+}
+
+/// Dinucleotide frequencies as a percent of all counted dinucleotides,
+/// matching `calcDinuclFreq` in GenomicDistributions.
+pub fn calc_dinucl_freq(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+) -> Result<HashMap<Dinucleotide, f64>> {
+    let dinucl_counts = calc_dinucl_counts(region_set, genome)?;
+    let total: u64 = dinucl_counts.values().sum();
+
+    let dinucl_freqs = dinucl_counts
+        .into_iter()
+        .map(|(dinucl, count)| (dinucl, count as f64 / total as f64 * 100.0))
+        .collect();
+
     Ok(dinucl_freqs)
 }
 
+/// Per-region dinucleotide frequencies (percent of the dinucleotides counted in
+/// each region), one row per region in input order with columns in
+/// `Dinucleotide::ALL` order. Regions without a single counted dinucleotide get
+/// NaN frequencies.
+pub fn calc_dinucl_freq_per_region(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+    raw_counts: bool,
+) -> Result<RegionMatrix> {
+    let columns = Dinucleotide::ALL
+        .iter()
+        .map(|dinucl| Ok(dinucl.to_string()?.to_uppercase()))
+        .collect::<Result<Vec<String>>>()?;
+    let mut matrix = RegionMatrix::new(columns);
+
+    for region in region_set.iter() {
+        let seq = genome.seq_from_region(region)?;
+
+        let mut counts = [0.0; 16];
+        for aas in seq.windows(2) {
+            if let Some(dinucl) = Dinucleotide::from_bytes(aas) {
+                counts[dinucl as usize] += 1.0;
+            }
+        }
+
+        if !raw_counts {
+            let total: f64 = counts.iter().sum();
+            for count in counts.iter_mut() {
+                *count = *count / total * 100.0;
+            }
+        }
+
+        matrix.push_row(region, &counts)?;
+    }
+
+    Ok(matrix)
+}
+
+pub fn calc_kmer_counts(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+    k: usize,
+    canonical: bool,
+    mask: MaskFilter,
+) -> Result<KmerCounts> {
+    let mut kmer_counts = KmerCounts::new(k, canonical)?;
+
+    for region in region_set.iter() {
+        let seq = genome.seq_from_region(region)?;
+        kmer_counts.count_seq_masked(seq, mask);
+    }
+
+    Ok(kmer_counts)
+}
+
+/// Per-region k-mer spectra, one row per region in input order. Frequencies are
+/// a percent of the k-mers counted in each region, or NaN if there were none.
+pub fn calc_kmer_freq_per_region(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+    k: usize,
+    canonical: bool,
+    mask: MaskFilter,
+    raw_counts: bool,
+) -> Result<RegionMatrix> {
+    let mut kmer_counts = KmerCounts::new(k, canonical)?;
+    let mut matrix = RegionMatrix::new(kmer_counts.kmers());
+
+    for region in region_set.iter() {
+        let seq = genome.seq_from_region(region)?;
+
+        kmer_counts.clear();
+        kmer_counts.count_seq_masked(seq, mask);
+
+        let total = kmer_counts.total() as f64;
+        let row = kmer_counts
+            .values()
+            .into_iter()
+            .map(|count| match raw_counts {
+                true => count as f64,
+                false => count as f64 / total * 100.0,
+            })
+            .collect::<Vec<f64>>();
+
+        matrix.push_row(region, &row)?;
+    }
+
+    Ok(matrix)
+}
+
+/// Soft-masked (lowercase) fraction of each region in input order, `None`
+/// for zero-width regions.
+pub fn calc_repeat_fraction(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+) -> Result<Vec<Option<f64>>> {
+    let mut repeat_fractions = Vec::with_capacity(region_set.len());
+
+    for region in region_set.iter() {
+        let seq = genome.seq_from_region(region)?;
+        let masked_count = seq.iter().filter(|base| base.is_ascii_lowercase()).count();
+
+        repeat_fractions.push((!seq.is_empty()).then(|| masked_count as f64 / seq.len() as f64));
+    }
+
+    Ok(repeat_fractions)
+}
+
+/// Sequence complexity of each region in input order: k-mer entropy, DUST
+/// score, longest homopolymer and dinucleotide-repeat fraction. N and other
+/// ambiguous bases break k-mers, triplets, homopolymers and repeats.
+pub fn calc_complexity(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+    k: usize,
+) -> Result<Vec<ComplexityStats>> {
+    let mut kmer_counts = KmerCounts::new(k, false)?;
+    let mut complexity_stats = Vec::with_capacity(region_set.len());
+
+    for region in region_set.iter() {
+        let seq = genome.seq_from_region(region)?;
+
+        kmer_counts.clear();
+        kmer_counts.count_seq(seq);
+        let total = kmer_counts.total() as f64;
+        let entropy = match total > 0.0 {
+            true => kmer_counts
+                .values()
+                .into_iter()
+                .filter(|count| *count > 0)
+                .map(|count| {
+                    let p = count as f64 / total;
+                    -p * p.log2()
+                })
+                .sum(),
+            false => f64::NAN,
+        };
+
+        complexity_stats.push(ComplexityStats {
+            region: region.clone(),
+            entropy,
+            dust_score: dust_score(seq),
+            longest_homopolymer: longest_homopolymer(seq),
+            dinucleotide_repeat_fraction: dinucleotide_repeat_fraction(seq),
+        });
+    }
+
+    Ok(complexity_stats)
+}
+
+// highest symmetric DUST score, sum(c_t * (c_t - 1) / 2) / (l - 1) over the l
+// triplets of each window, NaN when no window holds two triplets
+fn dust_score(seq: &[u8]) -> f64 {
+    let triplets: Vec<Option<u32>> = seq.windows(3).map(encode_kmer).collect();
+    let window = DUST_WINDOW - 2;
+
+    let mut counts = [0u32; 64];
+    let mut n_triplets: u32 = 0;
+    let mut score_sum: u32 = 0;
+    let mut best = f64::NAN;
+
+    for (i, triplet) in triplets.iter().enumerate() {
+        if let Some(code) = triplet {
+            score_sum += counts[*code as usize];
+            counts[*code as usize] += 1;
+            n_triplets += 1;
+        }
+        if i >= window {
+            if let Some(code) = triplets[i - window] {
+                counts[code as usize] -= 1;
+                score_sum -= counts[code as usize];
+                n_triplets -= 1;
+            }
+        }
+
+        // score full windows, or the whole sequence when it is shorter than one
+        if (i + 1 >= window || i + 1 == triplets.len()) && n_triplets > 1 {
+            let score = score_sum as f64 / (n_triplets - 1) as f64;
+            best = match best.is_nan() {
+                true => score,
+                false => best.max(score),
+            };
+        }
+    }
+
+    best
+}
+
+fn longest_homopolymer(seq: &[u8]) -> u32 {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut prev: u8 = 0;
+
+    for base in seq.iter().map(|base| base.to_ascii_uppercase()) {
+        run = match matches!(base, b'A' | b'C' | b'G' | b'T') {
+            true if base == prev => run + 1,
+            true => 1,
+            false => 0,
+        };
+        prev = base;
+        longest = longest.max(run);
+    }
+
+    longest
+}
+
+// fraction of bases in runs of at least DINUCLEOTIDE_REPEAT_MIN_UNITS copies of a
+// two base unit made of different bases, e.g. CACACACA
+fn dinucleotide_repeat_fraction(seq: &[u8]) -> f64 {
+    if seq.is_empty() {
+        return f64::NAN;
+    }
+
+    let seq: Vec<u8> = seq.iter().map(|base| base.to_ascii_uppercase()).collect();
+    let is_base = |base: u8| matches!(base, b'A' | b'C' | b'G' | b'T');
+    let min_length = 2 * DINUCLEOTIDE_REPEAT_MIN_UNITS;
+
+    let mut repeat_bases = 0;
+    let mut i = 0;
+    while i + 1 < seq.len() {
+        if !is_base(seq[i]) || !is_base(seq[i + 1]) || seq[i] == seq[i + 1] {
+            i += 1;
+            continue;
+        }
+
+        let mut end = i + 2;
+        while end < seq.len() && seq[end] == seq[end - 2] {
+            end += 1;
+        }
+
+        if end - i >= min_length {
+            repeat_bases += end - i;
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+
+    repeat_bases as f64 / seq.len() as f64
+}
+
+/// CpG count, density and observed/expected ratio for each region in input
+/// order. Density is NaN for zero-width regions and the ratio is NaN when a
+/// region has no C or no G.
+pub fn calc_cpg_oe(region_set: &RegionSet, genome: &GenomeAssembly) -> Result<Vec<CpgStats>> {
+    let mut cpg_stats = Vec::with_capacity(region_set.len());
+
+    for region in region_set.iter() {
+        let seq = genome.seq_from_region(region)?;
+
+        let mut c_count: u32 = 0;
+        let mut g_count: u32 = 0;
+        let mut cpg_count: u32 = 0;
+        let mut prev: u8 = 0;
+
+        for base in seq {
+            let base = base.to_ascii_uppercase();
+            match base {
+                b'C' => c_count += 1,
+                b'G' => {
+                    g_count += 1;
+                    if prev == b'C' {
+                        cpg_count += 1;
+                    }
+                }
+                _ => {}
+            }
+            prev = base;
+        }
+
+        let length = seq.len() as f64;
+        let cpg_oe = match c_count > 0 && g_count > 0 {
+            true => cpg_count as f64 * length / (c_count as f64 * g_count as f64),
+            false => f64::NAN,
+        };
+
+        cpg_stats.push(CpgStats {
+            region: region.clone(),
+            cpg_count,
+            cpg_density: cpg_count as f64 / length,
+            cpg_oe,
+        });
+    }
+
+    Ok(cpg_stats)
+}
+
+// the (possibly resized and reverse complemented) sequence of a region and its record name
+fn region_record(
+    region: &Region,
+    genome: &GenomeAssembly,
+    options: &FastaOptions,
+) -> Result<(String, Vec<u8>)> {
+    let region = match options.resize {
+        Some(width) => {
+            let chrom_size = genome.chrom_length(&region.chr).ok_or_else(|| {
+                anyhow::anyhow!("Unknown chromosome found in region set: {}", region.chr)
+            })?;
+            region.resize_center(width, chrom_size)
+        }
+        None => region.clone(),
+    };
+
+    let seq = genome.seq_from_region(&region)?;
+    let seq = match options.stranded && region.strand == Strand::Minus {
+        true => reverse_complement(seq),
+        false => seq.to_vec(),
+    };
+
+    let name = match (options.name_by, &region.name) {
+        (FastaName::Name, Some(name)) => name.to_string(),
+        _ => match options.stranded {
+            true => format!(
+                "{}:{}-{}({})",
+                region.chr, region.start, region.end, region.strand
+            ),
+            false => format!("{}:{}-{}", region.chr, region.start, region.end),
+        },
+    };
+
+    Ok((name, seq))
+}
+
+/// Name and sequence of each region in input order.
+pub fn extract_sequences(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+    options: &FastaOptions,
+) -> Result<Vec<(String, Vec<u8>)>> {
+    region_set
+        .iter()
+        .map(|region| region_record(region, genome, options))
+        .collect()
+}
+
+/// Writes the sequence of each region as an unwrapped FASTA record.
+pub fn write_fasta<W: Write>(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+    options: &FastaOptions,
+    writer: &mut W,
+) -> Result<()> {
+    for region in region_set.iter() {
+        let (name, seq) = region_record(region, genome, options)?;
+        writer.write_all(format!(">{}\n", name).as_bytes())?;
+        writer.write_all(&seq)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Calls CpG islands inside each region, or across every chromosome when no
+/// region set is given. Windows of `min_length` bases that meet the GC and o/e
+/// criteria are merged, trimmed to start and end on a CpG, and shrunk from
+/// the 3' end until the whole island meets the criteria again. Windows never
+/// span N. Islands come back sorted and free of duplicates.
+pub fn find_cpg_islands(
+    genome: &GenomeAssembly,
+    region_set: Option<&RegionSet>,
+    criteria: &CpgIslandCriteria,
+) -> Result<RegionSet> {
+    ensure!(
+        criteria.min_length >= 2,
+        "CpG island minimum length must be at least 2, got {}",
+        criteria.min_length
+    );
+
+    let regions: Vec<Region> = match region_set {
+        Some(region_set) => region_set.iter().cloned().collect(),
+        None => chromosome_regions(genome),
+    };
+
+    let mut islands = Vec::new();
+    for region in regions.iter() {
+        let seq = genome.seq_from_region(region)?;
+        for (start, end) in cpg_islands_in_seq(seq, criteria) {
+            islands.push(Region::new(
+                &region.chr,
+                region.start + start as u32,
+                region.start + end as u32,
+            ));
+        }
+    }
+
+    islands.sort_by(|a, b| (&a.chr, a.start, a.end).cmp(&(&b.chr, b.start, b.end)));
+    islands.dedup();
+
+    Ok(RegionSet::from_regions(islands).into_sorted())
+}
+
+// one region spanning each chromosome, sorted by name
+fn chromosome_regions(genome: &GenomeAssembly) -> Vec<Region> {
+    let mut chroms: Vec<(String, u32)> = genome
+        .chrom_sizes()
+        .iter()
+        .map(|(chr, size)| (chr.to_string(), *size))
+        .collect();
+    chroms.sort();
+    chroms
+        .into_iter()
+        .map(|(chr, size)| Region::new(&chr, 0, size))
+        .collect()
+}
+
+/// Runs of at least `min_length` N bases across every chromosome, sorted by
+/// chromosome name and position. Soft-masked `n` counts as N, other
+/// ambiguity codes do not.
+pub fn find_gaps(genome: &GenomeAssembly, min_length: u32) -> Result<RegionSet> {
+    ensure!(
+        min_length >= 1,
+        "Gap minimum length must be at least 1, got {}",
+        min_length
+    );
+
+    let mut gaps = Vec::new();
+    for chrom in chromosome_regions(genome) {
+        let seq = genome.seq_from_region(&chrom)?;
+
+        let mut run_start: Option<usize> = None;
+        for (i, base) in seq.iter().chain(std::iter::once(&b'A')).enumerate() {
+            match (base.eq_ignore_ascii_case(&b'N'), run_start) {
+                (true, None) => run_start = Some(i),
+                (false, Some(start)) => {
+                    if i - start >= min_length as usize {
+                        gaps.push(Region::new(&chrom.chr, start as u32, i as u32));
+                    }
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(RegionSet::from_regions(gaps).into_sorted())
+}
+
+/// Cuts every chromosome whose class is in `keep` at the sites of all
+/// `enzymes` and returns the fragments sorted by chromosome name and
+/// position. Each fragment is named by its 0-based fragment ID, its index in
+/// the returned set.
+pub fn digest_genome(
+    genome: &GenomeAssembly,
+    enzymes: &[RestrictionEnzyme],
+    keep: &[ChromClass],
+) -> Result<RegionSet> {
+    ensure!(!enzymes.is_empty(), "At least one enzyme is required");
+
+    let mut fragments = Vec::new();
+    for chrom in chromosome_regions(genome)
+        .into_iter()
+        .filter(|chrom| keep.contains(&ChromClass::from_chr(&chrom.chr)))
+    {
+        let seq = genome.seq_from_region(&chrom)?;
+
+        let mut cuts = vec![0, seq.len()];
+        for enzyme in enzymes {
+            cuts.extend(enzyme.cut_positions(seq)?);
+        }
+        cuts.sort_unstable();
+        cuts.dedup();
+
+        for bounds in cuts.windows(2) {
+            fragments.push(Region {
+                name: Some(fragments.len().to_string()),
+                ..Region::new(&chrom.chr, bounds[0] as u32, bounds[1] as u32)
+            });
+        }
+    }
+
+    Ok(RegionSet::from_regions(fragments).into_sorted())
+}
+
+/// IDs of the fragments each region overlaps, in input order. Fragment IDs
+/// are positions in `fragments` in input order, and zero-width regions map to
+/// the fragment containing them.
+pub fn map_to_fragments(region_set: &RegionSet, fragments: &RegionSet) -> Vec<Vec<usize>> {
+    let mut intervals: HashMap<&str, Vec<Interval<u32, usize>>> = HashMap::new();
+    for (id, fragment) in fragments.iter().enumerate() {
+        intervals
+            .entry(fragment.chr.as_str())
+            .or_default()
+            .push(Interval {
+                start: fragment.start,
+                stop: fragment.end,
+                val: id,
+            });
+    }
+    let trees: HashMap<&str, Lapper<u32, usize>> = intervals
+        .into_iter()
+        .map(|(chr, intervals)| (chr, Lapper::new(intervals)))
+        .collect();
+
+    region_set
+        .iter()
+        .map(|region| match trees.get(region.chr.as_str()) {
+            Some(tree) => {
+                let mut ids: Vec<usize> = tree
+                    .find(region.start, region.end.max(region.start + 1))
+                    .map(|hit| hit.val)
+                    .collect();
+                ids.sort_unstable();
+                ids
+            }
+            None => Vec::new(),
+        })
+        .collect()
+}
+
+// running base composition of a stretch of sequence
+#[derive(Default)]
+struct CpgCounts {
+    c: u32,
+    g: u32,
+    cpg: u32,
+    other: u32,
+}
+
+impl CpgCounts {
+    fn passes(&self, len: usize, criteria: &CpgIslandCriteria) -> bool {
+        if self.other > 0 || len < criteria.min_length as usize || self.c == 0 || self.g == 0 {
+            return false;
+        }
+        let gc = (self.c + self.g) as f64 / len as f64;
+        let oe = self.cpg as f64 * len as f64 / (self.c as f64 * self.g as f64);
+        gc >= criteria.min_gc && oe >= criteria.min_oe
+    }
+}
+
+// start and end offsets of the CpG islands in `seq`
+fn cpg_islands_in_seq(seq: &[u8], criteria: &CpgIslandCriteria) -> Vec<(usize, usize)> {
+    let window = criteria.min_length as usize;
+    if seq.len() < window {
+        return Vec::new();
+    }
+
+    let base = |i: usize| seq[i].to_ascii_uppercase();
+    let is_cpg = |i: usize| base(i) == b'C' && base(i + 1) == b'G';
+
+    // adds (+1) or removes (-1) base i from the C, G and non-ACGT counts
+    let update_base = |counts: &mut CpgCounts, i: usize, delta: i32| {
+        let value = match base(i) {
+            b'C' => &mut counts.c,
+            b'G' => &mut counts.g,
+            b'A' | b'T' => return,
+            _ => &mut counts.other,
+        };
+        *value = value.wrapping_add_signed(delta);
+    };
+
+    // merge every passing window into candidate stretches
+    let mut candidates: Vec<(usize, usize)> = Vec::new();
+    let mut counts = CpgCounts::default();
+    for i in 0..window {
+        update_base(&mut counts, i, 1);
+        if i > 0 && is_cpg(i - 1) {
+            counts.cpg += 1;
+        }
+    }
+    for start in 0..=seq.len() - window {
+        if start > 0 {
+            update_base(&mut counts, start - 1, -1);
+            if is_cpg(start - 1) {
+                counts.cpg -= 1;
+            }
+            let end = start + window;
+            update_base(&mut counts, end - 1, 1);
+            if is_cpg(end - 2) {
+                counts.cpg += 1;
+            }
+        }
+
+        if counts.passes(window, criteria) {
+            match candidates.last_mut() {
+                Some(last) if start <= last.1 => last.1 = start + window,
+                _ => candidates.push((start, start + window)),
+            }
+        }
+    }
+
+    let mut islands = Vec::new();
+    for (mut start, mut end) in candidates {
+        let mut counts = CpgCounts::default();
+        for i in start..end {
+            update_base(&mut counts, i, 1);
+            if i + 1 < end && is_cpg(i) {
+                counts.cpg += 1;
+            }
+        }
+
+        loop {
+            // trim both ends back to a CpG, neither trimmed base is part of one
+            while end - start >= 2 && !is_cpg(start) {
+                update_base(&mut counts, start, -1);
+                start += 1;
+            }
+            while end - start >= 2 && !is_cpg(end - 2) {
+                update_base(&mut counts, end - 1, -1);
+                end -= 1;
+            }
+
+            if counts.passes(end - start, criteria) {
+                islands.push((start, end));
+                break;
+            }
+            if end - start <= window {
+                break;
+            }
+
+            // shrink from the 3' end, breaking the final CpG
+            update_base(&mut counts, end - 1, -1);
+            counts.cpg -= 1;
+            end -= 1;
+        }
+    }
+
+    islands
+}
+
+/// One-hot encodes the sequence of each region in input order. N and other
+/// ambiguous bases encode as all zeros. Every sequence must have the same
+/// width, so regions of differing widths need `resize`. With
+/// `reverse_complement` the reverse complements follow the forward sequences,
+/// doubling `n_seqs`.
+pub fn one_hot_encode(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+    options: &OneHotOptions,
+) -> Result<OneHotSequences> {
+    let fasta_options = FastaOptions {
+        stranded: options.stranded,
+        resize: options.resize,
+        ..Default::default()
+    };
+
+    let mut seqs: Vec<Vec<u8>> = Vec::with_capacity(region_set.len());
+    for region in region_set.iter() {
+        let (name, seq) = region_record(region, genome, &fasta_options)?;
+        if let Some(first) = seqs.first() {
+            ensure!(
+                seq.len() == first.len(),
+                "Region {} has width {} but earlier regions have width {}. Resize to a fixed width to one-hot encode regions of different widths",
+                name,
+                seq.len(),
+                first.len()
+            );
+        }
+        seqs.push(seq);
+    }
+
+    if options.reverse_complement {
+        let reverse: Vec<Vec<u8>> = seqs.iter().map(|seq| reverse_complement(seq)).collect();
+        seqs.extend(reverse);
+    }
+
+    let width = seqs.first().map_or(0, |seq| seq.len());
+    let mut values = vec![0u8; seqs.len() * width * 4];
+    for (i, seq) in seqs.iter().enumerate() {
+        for (j, base) in seq.iter().enumerate() {
+            let channel = match base.to_ascii_uppercase() {
+                b'A' => 0,
+                b'C' => 1,
+                b'G' => 2,
+                b'T' => 3,
+                _ => continue,
+            };
+            values[(i * width + j) * 4 + channel] = 1;
+        }
+    }
+
+    Ok(OneHotSequences {
+        n_seqs: seqs.len(),
+        width,
+        values,
+    })
+}
+
+/// Scans both strands of every region for each motif, keeping hits with a
+/// p-value of at most `p_value` under a uniform background.
+pub fn scan_motifs(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+    motifs: &[Motif],
+    p_value: f64,
+) -> Result<MotifScan> {
+    ensure!(
+        p_value > 0.0 && p_value <= 1.0,
+        "p-value threshold must be in (0, 1], got {}",
+        p_value
+    );
+
+    let pwms: Vec<Pwm> = motifs.iter().map(Pwm::new).collect();
+    let thresholds: Vec<i32> = pwms.iter().map(|pwm| pwm.threshold(p_value)).collect();
+
+    let motif_ids: Vec<String> = motifs.iter().map(|motif| motif.id.to_string()).collect();
+    let mut counts = RegionMatrix::new(motif_ids.clone());
+    let mut hits = Vec::new();
+
+    for (region_index, region) in region_set.iter().enumerate() {
+        let seq = genome.seq_from_region(region)?;
+        let mut row = vec![0.0; motifs.len()];
+
+        for (motif_index, (pwm, threshold)) in pwms.iter().zip(thresholds.iter()).enumerate() {
+            for (offset, strand, score) in pwm.scan(seq, *threshold) {
+                row[motif_index] += 1.0;
+
+                let start = region.start + offset as u32;
+                hits.push(MotifHit {
+                    region_index,
+                    motif_index,
+                    chr: region.chr.to_string(),
+                    start,
+                    end: start + pwm.width() as u32,
+                    strand,
+                    score: Pwm::to_bits(score),
+                    p_value: pwm.p_value(score),
+                });
+            }
+        }
+
+        counts.push_row(region, &row)?;
+    }
+
+    Ok(MotifScan {
+        motifs: motif_ids,
+        counts,
+        hits,
+    })
+}
+
+/// Random regions matching the width and GC content bin of each query region,
+/// `ratio` per query. Windows containing N or overlapping a query region are
+/// never drawn, and queries without informative bases get no background.
+pub fn sample_gc_matched_background(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+    ratio: usize,
+    gc_bin_width: f64,
+    seed: u64,
+) -> Result<RegionSet> {
+    ensure!(
+        gc_bin_width > 0.0 && gc_bin_width <= 1.0,
+        "GC bin width must be in (0, 1], got {}",
+        gc_bin_width
+    );
+
+    // the small offset keeps values like 0.6 / 0.05 out of the bin below
+    let gc_bin = |gc: f64| (gc / gc_bin_width + 1e-9).floor() as i64;
+    let options = GcContentOptions {
+        exclude_ambiguous: true,
+        ..Default::default()
+    };
+
+    // query regions by the genome's chromosome names, so aliases still overlap
+    let mut intervals: HashMap<&str, Vec<Interval<u32, ()>>> = HashMap::new();
+    for region in region_set.iter() {
+        let chr = genome.get_key(&region.chr).unwrap_or(&region.chr);
+        intervals.entry(chr).or_default().push(Interval {
+            start: region.start,
+            stop: region.end,
+            val: (),
+        });
+    }
+    let queries: HashMap<&str, Lapper<u32, ()>> = intervals
+        .into_iter()
+        .map(|(chr, intervals)| (chr, Lapper::new(intervals)))
+        .collect();
+    let overlaps_query = |window: &Region| match queries.get(window.chr.as_str()) {
+        Some(tree) => tree.find(window.start, window.end).next().is_some(),
+        None => false,
+    };
+
+    let mut sampler = WindowSampler::new(genome, seed)?;
+    let mut background = Vec::with_capacity(region_set.len() * ratio);
+
+    for region in region_set.iter() {
+        let seq = genome.seq_from_region(region)?;
+        let Some(gc) = gc_fraction(seq, &options) else {
+            continue;
+        };
+
+        let bin = gc_bin(gc);
+        let mut found = 0;
+        let mut attempts = 0;
+        while found < ratio && attempts < ratio * BACKGROUND_ATTEMPTS {
+            attempts += 1;
+
+            let Some(window) = sampler.sample(region.width()) else {
+                continue;
+            };
+            if overlaps_query(&window) {
+                continue;
+            }
+            let (gc_count, n_count, total_count) =
+                count_gc(genome.seq_from_region(&window)?, MaskFilter::All);
+            if n_count == 0 && gc_bin(gc_count as f64 / total_count as f64) == bin {
+                background.push(window);
+                found += 1;
+            }
+        }
+    }
+
+    Ok(RegionSet::from_regions(background))
+}
+
+/// Fraction of regions with a hit for each motif, against a GC- and
+/// width-matched background drawn from the same genome. Query regions without
+/// informative bases have no background and are left out. Results are in
+/// motif order.
+pub fn calc_motif_enrichment(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+    motifs: &[Motif],
+    options: &MotifEnrichmentOptions,
+) -> Result<Vec<MotifEnrichment>> {
+    // the same queries sample_gc_matched_background draws a background for
+    let gc_options = GcContentOptions {
+        exclude_ambiguous: true,
+        ..Default::default()
+    };
+    let mut informative: Vec<Region> = vec![];
+    for region in region_set.iter() {
+        if gc_fraction(genome.seq_from_region(region)?, &gc_options).is_some() {
+            informative.push(region.clone());
+        }
+    }
+    let region_set = &RegionSet::from_regions(informative);
+
+    let background = sample_gc_matched_background(
+        region_set,
+        genome,
+        options.background_ratio,
+        options.gc_bin_width,
+        options.seed,
+    )?;
+    ensure!(
+        !background.is_empty(),
+        "No GC-matched background regions could be drawn from the genome"
+    );
+
+    let query_hits = regions_with_hits(&scan_motifs(region_set, genome, motifs, options.p_value)?);
+    let background_hits =
+        regions_with_hits(&scan_motifs(&background, genome, motifs, options.p_value)?);
+
+    let query_total = region_set.len();
+    let background_total = background.len();
+    let ln_fact = ln_factorials(query_total + background_total);
+
+    let p_values: Vec<f64> = query_hits
+        .iter()
+        .zip(background_hits.iter())
+        .map(|(query, background)| {
+            hypergeometric_sf(
+                *query,
+                query_total,
+                query + background,
+                query_total + background_total,
+                &ln_fact,
+            )
+        })
+        .collect();
+    let q_values = benjamini_hochberg(&p_values);
+
+    Ok(motifs
+        .iter()
+        .enumerate()
+        .map(|(i, motif)| MotifEnrichment {
+            motif: motif.id.to_string(),
+            query_hits: query_hits[i],
+            query_total,
+            background_hits: background_hits[i],
+            background_total,
+            fold_enrichment: (query_hits[i] as f64 / query_total as f64)
+                / (background_hits[i] as f64 / background_total as f64),
+            p_value: p_values[i],
+            q_value: q_values[i],
+        })
+        .collect())
+}
+
+// number of regions with at least one hit, per motif
+fn regions_with_hits(scan: &MotifScan) -> Vec<usize> {
+    let mut counts = vec![0; scan.motifs.len()];
+    for i in 0..scan.counts.n_rows() {
+        for (motif, hits) in scan.counts.row(i).iter().enumerate() {
+            if *hits > 0.0 {
+                counts[motif] += 1;
+            }
+        }
+    }
+    counts
+}
+
+// ln(k!) for k in 0..=n
+fn ln_factorials(n: usize) -> Vec<f64> {
+    let mut ln_fact = vec![0.0; n + 1];
+    for k in 1..=n {
+        ln_fact[k] = ln_fact[k - 1] + (k as f64).ln();
+    }
+    ln_fact
+}
+
+// P(X >= k) when drawing `draws` items out of `total`, `successes` of which are successes
+fn hypergeometric_sf(
+    k: usize,
+    draws: usize,
+    successes: usize,
+    total: usize,
+    ln_fact: &[f64],
+) -> f64 {
+    let ln_choose = |n: usize, k: usize| ln_fact[n] - ln_fact[k] - ln_fact[n - k];
+    let failures = total - successes;
+    let ln_denominator = ln_choose(total, draws);
+
+    let mut p_value = 0.0;
+    for x in k..=successes.min(draws) {
+        if draws - x > failures {
+            continue;
+        }
+        p_value +=
+            (ln_choose(successes, x) + ln_choose(failures, draws - x) - ln_denominator).exp();
+    }
+    p_value.min(1.0)
+}
+
+fn benjamini_hochberg(p_values: &[f64]) -> Vec<f64> {
+    let n = p_values.len();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|a, b| p_values[*b].total_cmp(&p_values[*a]));
+
+    let mut q_values = vec![0.0; n];
+    let mut running_min: f64 = 1.0;
+    for (i, index) in order.into_iter().enumerate() {
+        let rank = n - i;
+        running_min = running_min.min(p_values[index] * n as f64 / rank as f64);
+        q_values[index] = running_min;
+    }
+    q_values
+}
+
+/// Finds every match of each pattern in each region. With `both_strands`,
+/// the reverse complement is searched too; a minus-strand match at the same
+/// coordinates as a plus-strand one (a palindromic site) is only reported once.
+pub fn search_patterns(
+    region_set: &RegionSet,
+    genome: &GenomeAssembly,
+    patterns: &[Pattern],
+    both_strands: bool,
+) -> Result<PatternScan> {
+    let names: Vec<String> = patterns.iter().map(|p| p.name.to_string()).collect();
+    let mut counts = RegionMatrix::new(names.clone());
+    let mut hits = Vec::new();
+
+    for (region_index, region) in region_set.iter().enumerate() {
+        let seq = genome.seq_from_region(region)?;
+        let rc_seq = match both_strands {
+            true => reverse_complement(seq),
+            false => Vec::new(),
+        };
+        let mut row = vec![0.0; patterns.len()];
+
+        for (pattern_index, pattern) in patterns.iter().enumerate() {
+            let plus = pattern.find_all(seq);
+            let plus_coords: HashSet<(usize, usize)> = plus.iter().copied().collect();
+
+            // map reverse complement coordinates back onto the plus strand
+            let minus: Vec<(usize, usize)> = pattern
+                .find_all(&rc_seq)
+                .into_iter()
+                .map(|(start, end)| (seq.len() - end, seq.len() - start))
+                .filter(|coords| !plus_coords.contains(coords))
+                .collect();
+
+            let stranded = plus
+                .into_iter()
+                .map(|coords| (coords, Strand::Plus))
+                .chain(minus.into_iter().map(|coords| (coords, Strand::Minus)));
+
+            for ((start, end), strand) in stranded {
+                row[pattern_index] += 1.0;
+                hits.push(PatternHit {
+                    region_index,
+                    pattern_index,
+                    chr: region.chr.to_string(),
+                    start: region.start + start as u32,
+                    end: region.start + end as u32,
+                    strand,
+                });
+            }
+        }
+
+        counts.push_row(region, &row)?;
+    }
+
+    Ok(PatternScan {
+        patterns: names,
+        counts,
+        hits,
+    })
+}
+
 pub fn calc_tss_dist(region_set: &RegionSet, tss_index: &TSSIndex) -> Result<Vec<u32>> {
     let mut tss_dists: Vec<u32> = Vec::with_capacity(region_set.len());
 
@@ -151,11 +1388,198 @@ pub fn calc_tss_dist(region_set: &RegionSet, tss_index: &TSSIndex) -> Result<Vec
     Ok(tss_dists)
 }
 
+pub fn detect_assembly(region_set: &RegionSet) -> Result<AssemblyGuess> {
+    ensure!(
+        !region_set.is_empty(),
+        "Can't detect the assembly of an empty region set!"
+    );
+
+    let aliases = ChromAliases::builtin();
+    let tables = BUILTIN_ASSEMBLIES
+        .iter()
+        .map(|assembly| Ok((assembly.to_string(), ChromSizes::builtin(assembly)?)))
+        .collect::<Result<Vec<(String, ChromSizes)>>>()?;
+
+    // figure out the dominant naming convention by region count
+    let mut naming_counts: HashMap<ChromNaming, usize> = HashMap::new();
+    for chr in region_set.iter_chroms() {
+        *naming_counts.entry(ChromNaming::from_chr(chr)).or_insert(0) +=
+            region_set.iter_regions(chr).count();
+    }
+    let naming = naming_counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(naming, _)| naming)
+        .unwrap();
+
+    let mut candidates: Vec<AssemblyCandidate> = tables
+        .iter()
+        .map(|(assembly, _)| AssemblyCandidate {
+            assembly: assembly.to_string(),
+            n_compatible: 0,
+            n_incompatible: 0,
+            probability: 0.0,
+        })
+        .collect();
+    let mut n_unplaced = 0;
+
+    for chr in region_set.iter_chroms() {
+        let ucsc_chr = aliases.canonical(chr);
+
+        // regions on contigs none of the tables know about carry no information
+        if !tables
+            .iter()
+            .any(|(_, sizes)| sizes.contains_chr(&ucsc_chr))
+        {
+            n_unplaced += region_set.iter_regions(chr).count();
+            continue;
+        }
+
+        for (candidate, (_, sizes)) in candidates.iter_mut().zip(tables.iter()) {
+            let size = sizes.get(&ucsc_chr);
+            for region in region_set.iter_regions(chr) {
+                match size {
+                    Some(size) if region.start <= region.end && region.end <= size => {
+                        candidate.n_compatible += 1
+                    }
+                    _ => candidate.n_incompatible += 1,
+                }
+            }
+        }
+    }
+
+    ensure!(
+        candidates
+            .iter()
+            .any(|c| c.n_compatible + c.n_incompatible > 0),
+        "None of the chromosomes in the region set belong to a known assembly!"
+    );
+
+    // each region fits the true assembly unless it is junk, so an assembly's
+    // log-likelihood is driven by how many regions it can't place
+    let log_likelihoods: Vec<f64> = candidates
+        .iter()
+        .map(|c| {
+            c.n_compatible as f64 * (1.0 - ASSEMBLY_ERROR_RATE).ln()
+                + c.n_incompatible as f64 * ASSEMBLY_ERROR_RATE.ln()
+        })
+        .collect();
+    let max_ll = log_likelihoods
+        .iter()
+        .cloned()
+        .fold(f64::NEG_INFINITY, f64::max);
+    let total: f64 = log_likelihoods.iter().map(|ll| (ll - max_ll).exp()).sum();
+    for (candidate, ll) in candidates.iter_mut().zip(log_likelihoods) {
+        candidate.probability = (ll - max_ll).exp() / total;
+    }
+
+    // ties go to the newer assembly since the built-in list is ordered newest first
+    candidates.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+
+    let best = &candidates[0];
+    let best_sizes = &tables
+        .iter()
+        .find(|(assembly, _)| *assembly == best.assembly)
+        .unwrap()
+        .1;
+
+    let mut incompatible_regions = vec![];
+    for chr in region_set.iter_chroms() {
+        let ucsc_chr = aliases.canonical(chr);
+        if !tables
+            .iter()
+            .any(|(_, sizes)| sizes.contains_chr(&ucsc_chr))
+        {
+            continue;
+        }
+        let size = best_sizes.get(&ucsc_chr);
+        for region in region_set.iter_regions(chr) {
+            match size {
+                Some(size) if region.start <= region.end && region.end <= size => {}
+                _ => incompatible_regions.push(region.clone()),
+            }
+        }
+    }
+
+    Ok(AssemblyGuess {
+        assembly: best.assembly.to_string(),
+        naming,
+        confidence: best.probability,
+        candidates,
+        incompatible_regions,
+        n_unplaced,
+    })
+}
+
+/// Write `genome`, read from `input`, to `output` as a .2bit or memory-mapped
+/// file, with its digests in `<output>.seqcol`. Fails when `output` is the
+/// input file, since packed inputs stay mapped while they are read.
+pub fn pack_genome(
+    genome: &GenomeAssembly,
+    input: &Path,
+    output: &Path,
+    format: GenomeFormat,
+) -> Result<()> {
+    // truncating a mapped input for the output would pull the data from under us
+    let is_same_file = match (std::fs::canonicalize(input), std::fs::canonicalize(output)) {
+        (Ok(input), Ok(output)) => input == output,
+        _ => false,
+    };
+    ensure!(
+        !is_same_file,
+        "Output '{}' is the input genome file, choose another path",
+        output.to_string_lossy()
+    );
+
+    let mut file = BufWriter::new(File::create(output)?);
+    match format {
+        GenomeFormat::TwoBit => twobit::write_twobit(genome, &mut file)?,
+        GenomeFormat::Mapped => mapped::write_mapped(genome, &mut file)?,
+    }
+    file.flush()?;
+
+    // record the digests of what was packed next to it
+    refget::SequenceCollection::from_genome(genome)?.write_digests(output)
+}
+
 pub mod prelude {
+    pub use super::calc_complexity;
+    pub use super::calc_cpg_oe;
+    pub use super::calc_dinucl_counts;
     pub use super::calc_dinucl_freq;
+    pub use super::calc_dinucl_freq_per_region;
     pub use super::calc_gc_content;
+    pub use super::calc_gc_content_ref;
+    pub use super::calc_gc_content_stats;
+    pub use super::calc_kmer_counts;
+    pub use super::calc_kmer_freq_per_region;
+    pub use super::calc_motif_enrichment;
     pub use super::calc_neighbor_distances;
+    pub use super::calc_repeat_fraction;
     pub use super::calc_tss_dist;
     pub use super::calc_widths;
-    pub use super::models::{GenomeAssembly, Region, RegionSet, TSSIndex};
+    pub use super::detect_assembly;
+    pub use super::digest::RestrictionEnzyme;
+    pub use super::digest_genome;
+    pub use super::extract_sequences;
+    pub use super::find_cpg_islands;
+    pub use super::find_gaps;
+    pub use super::map_to_fragments;
+    pub use super::mapped::write_mapped;
+    pub use super::models::{
+        ChromAliases, ChromClass, ChromSizes, CpgIslandCriteria, FastaName, FastaOptions,
+        GcBackgroundMethod, GcContentOptions, GenomeAssembly, GenomeFormat, KmerCounts,
+        LengthSummary, MaskFilter, OneHotOptions, Region, RegionMatrix, RegionSet, Strand,
+        TSSIndex,
+    };
+    pub use super::motifs::{Motif, MotifEnrichmentOptions, MotifFormat};
+    pub use super::one_hot_encode;
+    pub use super::pack_genome;
+    pub use super::patterns::Pattern;
+    pub use super::refget::{refget_digest, AssemblyComparison, SequenceCollection};
+    pub use super::sample_gc_matched_background;
+    pub use super::scan_motifs;
+    pub use super::search_patterns;
+    pub use super::twobit::write_twobit;
+    pub use super::write_fasta;
 }
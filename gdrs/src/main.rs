@@ -1,12 +1,12 @@
 use anyhow::{ensure, Context, Result};
-use gdrs::models::GenomeAssembly;
+use gdrs::calc_neighbor_distances;
+use gdrs::models::{Dinucleotide, GenomeAssembly};
 use gdrs::prelude::*;
-use gdrs::{calc_gc_content, calc_neighbor_distances};
 use std::io::stdout;
 use std::io::Write;
 use std::path::Path;
 
-use clap::{arg, Command};
+use clap::{arg, ArgAction, ArgMatches, Command};
 
 pub mod consts {
     pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -14,27 +14,605 @@ pub mod consts {
     pub const BIN_NAME: &str = env!("CARGO_PKG_NAME");
     pub const ND_CMD: &str = "nd";
     pub const GC_CMD: &str = "gc";
+    pub const ASSEMBLY_CMD: &str = "assembly";
+    pub const DINUCL_CMD: &str = "dinucl";
+    pub const KMERS_CMD: &str = "kmers";
+    pub const CPG_CMD: &str = "cpg";
+    pub const GC_REF_CMD: &str = "gc-ref";
+    pub const REPEATS_CMD: &str = "repeats";
+    pub const GETFASTA_CMD: &str = "getfasta";
+    pub const MOTIFS_CMD: &str = "motifs";
+    pub const MOTIF_ENRICHMENT_CMD: &str = "motif-enrichment";
+    pub const PATTERNS_CMD: &str = "patterns";
+    pub const COMPLEXITY_CMD: &str = "complexity";
+    pub const CPG_ISLANDS_CMD: &str = "cpg-islands";
+    pub const GAPS_CMD: &str = "gaps";
+    pub const DIGEST_CMD: &str = "digest";
+    pub const GENOME_CMD: &str = "genome";
+    pub const GENOME_PACK_CMD: &str = "pack";
+    pub const GENOME_DIGEST_CMD: &str = "digest";
+    pub const GENOME_COMPARE_CMD: &str = "compare";
+    pub const BED_DIGEST_CMD: &str = "bed-digest";
+}
+
+fn with_chrom_filter_args(command: Command) -> Command {
+    command
+        .arg(
+            arg!(--canonical "Only keep regions on canonical chromosomes (autosomes, sex chromosomes and chrM)")
+                .required(false),
+        )
+        .arg(
+            arg!(--"drop-chroms" <CLASSES> "Comma separated chromosome classes to drop: autosome, sex, mito, random, un, alt, fix, decoy, ebv, other")
+                .required(false)
+                .value_delimiter(',')
+                .id("drop-chroms"),
+        )
+}
+
+fn with_mask_arg(command: Command) -> Command {
+    command.arg(
+        arg!(--mask <MASK> "Only use soft-masked (lowercase) or unmasked bases: all, unmasked, masked")
+            .required(false)
+            .default_value("all"),
+    )
+}
+
+fn get_mask(matches: &ArgMatches) -> Result<MaskFilter> {
+    matches
+        .get_one::<String>("mask")
+        .unwrap()
+        .parse::<MaskFilter>()
+}
+
+// chromosome classes kept by --canonical and --drop-chroms
+fn get_chrom_classes(matches: &ArgMatches) -> Result<Vec<ChromClass>> {
+    let mut keep: Vec<ChromClass> = match matches.get_flag("canonical") {
+        true => ChromClass::CANONICAL.to_vec(),
+        false => ChromClass::ALL.to_vec(),
+    };
+
+    if let Some(classes) = matches.get_many::<String>("drop-chroms") {
+        let dropped = classes
+            .map(|class| class.parse::<ChromClass>())
+            .collect::<Result<Vec<ChromClass>>>()?;
+        keep.retain(|class| !dropped.contains(class));
+    }
+
+    Ok(keep)
+}
+
+fn filter_chroms(region_set: RegionSet, matches: &ArgMatches) -> Result<RegionSet> {
+    let keep = get_chrom_classes(matches)?;
+
+    match keep.len() == ChromClass::ALL.len() {
+        true => Ok(region_set),
+        false => Ok(region_set.filter_chroms(&keep)),
+    }
 }
 
 fn build_neighbor_distances_cli() -> Command {
-    Command::new(consts::ND_CMD)
+    let command = Command::new(consts::ND_CMD)
         .author("Nathan LeRoy")
         .about("Calculate distances between consecutive regions in a query region set")
-        .arg(arg!(<path> "Path to bed file OR folder of bed files").required(true))
+        .arg(arg!(<path> "Path to bed file OR folder of bed files").required(true));
+    with_chrom_sizes_args(with_chrom_filter_args(command))
+}
+
+fn with_chrom_sizes_args(command: Command) -> Command {
+    command
+        .arg(
+            arg!(-c --"chrom-sizes" <CHROM_SIZES> "chrom.sizes or .fai file to validate regions against")
+                .required(false)
+                .id("chrom-sizes"),
+        )
+        .arg(
+            arg!(-a --assembly <ASSEMBLY> "Built-in assembly to validate regions against (e.g. hg38, or GRCh38 for Ensembl names)")
+                .required(false)
+                .conflicts_with("chrom-sizes"),
+        )
+}
+
+fn get_chrom_sizes(matches: &ArgMatches) -> Result<Option<ChromSizes>> {
+    if let Some(chrom_sizes) = matches.get_one::<String>("chrom-sizes") {
+        let chrom_sizes = ChromSizes::from_file(Path::new(chrom_sizes))
+            .with_context(|| format!("Failed to parse chrom sizes: '{}'", chrom_sizes))?;
+        return Ok(Some(chrom_sizes));
+    }
+
+    if let Some(assembly) = matches.get_one::<String>("assembly") {
+        return Ok(Some(ChromSizes::builtin(assembly)?));
+    }
+
+    Ok(None)
 }
 
 fn build_gc_content_cli() -> Command {
-    Command::new(consts::GC_CMD)
+    let command = Command::new(consts::GC_CMD)
         .author("Nathan LeRoy")
         .about("Compute the gc content of a query region set")
         .arg(arg!(<path> "Path to bed file OR folder of bed files").required(true))
-        .arg(arg!(-g --genome <GENOME> "genome assembly file").required(true))
         .arg(
-            arg!(-u --ignore-unused-chroms "Ignore any non-standard chromosomes in the bedfile")
+            arg!(-u --"ignore-unused-chroms" "Skip regions on chromosomes missing from the genome (use --canonical to drop non-standard chromosomes)")
                 .required(false)
                 .num_args(0)
                 .id("ignore-unk-chroms"),
         )
+        .arg(
+            arg!(--"exclude-n" "Exclude N and other ambiguous bases from the denominator")
+                .required(false)
+                .id("exclude-n"),
+        )
+        .arg(
+            arg!(--"n-fraction" "Also report the fraction of N/ambiguous bases in each region")
+                .required(false)
+                .id("n-fraction"),
+        );
+    with_chrom_sizes_args(with_mask_arg(with_chrom_filter_args(
+        with_genome_digest_arg(with_genome_args(command)),
+    )))
+}
+
+fn with_genome_digest_arg(command: Command) -> Command {
+    command.arg(
+        arg!(--"genome-digest" "Start the output with a '#seqcol' line holding the sequence collection digest of the genome (hashes the whole genome)")
+            .required(false)
+            .id("genome-digest"),
+    )
+}
+
+// writes the '#seqcol' provenance line when --genome-digest is set
+fn write_genome_digest<W: Write>(
+    writer: &mut W,
+    matches: &ArgMatches,
+    genome: &GenomeAssembly,
+) -> Result<()> {
+    if matches.get_flag("genome-digest") {
+        let collection = SequenceCollection::from_genome(genome)
+            .with_context(|| "Error computing genome digest")?;
+        writer.write_all(format!("#seqcol\t{}\n", collection.digest()).as_bytes())?;
+    }
+    Ok(())
+}
+
+fn with_genome_args(command: Command) -> Command {
+    command
+        .arg(arg!(-g --genome <GENOME> "genome assembly file: FASTA, .2bit or memory-mapped").required(true))
+        .arg(
+            arg!(--"chrom-aliases" <CHROM_ALIASES> "UCSC chromAlias file used to match region and genome chromosome names")
+                .required(false)
+                .id("chrom-aliases"),
+        )
+}
+
+fn get_chrom_aliases(matches: &ArgMatches) -> Result<ChromAliases> {
+    match matches.get_one::<String>("chrom-aliases") {
+        Some(path) => ChromAliases::from_file(Path::new(path))
+            .with_context(|| format!("Failed to parse chrom aliases: '{}'", path)),
+        None => Ok(ChromAliases::builtin()),
+    }
+}
+
+fn get_genome(matches: &ArgMatches) -> Result<GenomeAssembly> {
+    let genome = matches
+        .get_one::<String>("genome")
+        .expect("Please specify a genome assembly file");
+
+    let genome = Path::new(genome);
+    let genome = GenomeAssembly::from_file(genome)
+        .with_context(|| format!("Error reading genome file: '{}'", genome.to_string_lossy()))?
+        .with_aliases(&get_chrom_aliases(matches)?)?;
+
+    Ok(genome)
+}
+
+fn read_region_set(matches: &ArgMatches) -> Result<RegionSet> {
+    let path_to_data = matches
+        .get_one::<String>("path")
+        .expect("Path to data is required.");
+
+    let region_set = RegionSet::from_bed(Path::new(path_to_data))
+        .with_context(|| format!("Failed to parse bedfile: '{}'", path_to_data))?;
+
+    filter_chroms(region_set, matches)
+}
+
+// reads the filtered region set and checks it against --chrom-sizes/--assembly
+fn get_region_set(matches: &ArgMatches) -> Result<RegionSet> {
+    let region_set = read_region_set(matches)?;
+
+    if let Some(chrom_sizes) = get_chrom_sizes(matches)? {
+        chrom_sizes.validate(&region_set)?;
+    }
+
+    Ok(region_set)
+}
+
+fn build_assembly_cli() -> Command {
+    let command = Command::new(consts::ASSEMBLY_CMD)
+        .author("Nathan LeRoy")
+        .about("Guess the genome assembly of a query region set")
+        .arg(arg!(<path> "Path to bed file").required(true));
+    with_chrom_filter_args(command)
+}
+
+fn build_dinucl_freq_cli() -> Command {
+    let command = Command::new(consts::DINUCL_CMD)
+        .author("Nathan LeRoy")
+        .about("Compute dinucleotide frequencies of a query region set")
+        .arg(arg!(<path> "Path to bed file").required(true))
+        .arg(
+            arg!(--"per-region" "Write a region by dinucleotide matrix instead of genome-wide totals")
+                .required(false)
+                .id("per-region"),
+        )
+        .arg(
+            arg!(--"raw-counts" "Report raw counts instead of percent frequencies")
+                .required(false)
+                .id("raw-counts"),
+        );
+    with_chrom_sizes_args(with_chrom_filter_args(with_genome_digest_arg(
+        with_genome_args(command),
+    )))
+}
+
+fn build_kmer_freq_cli() -> Command {
+    let command = Command::new(consts::KMERS_CMD)
+        .author("Nathan LeRoy")
+        .about("Compute k-mer frequencies of a query region set")
+        .arg(arg!(<path> "Path to bed file").required(true))
+        .arg(
+            arg!(-k --k <K> "k-mer length (1-8)")
+                .required(false)
+                .default_value("3")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            arg!(--"fold-revcomp" "Count each k-mer together with its reverse complement")
+                .required(false)
+                .id("fold-revcomp"),
+        )
+        .arg(
+            arg!(--"per-region" "Write a region by k-mer matrix instead of genome-wide totals")
+                .required(false)
+                .id("per-region"),
+        )
+        .arg(
+            arg!(--"raw-counts" "Report raw counts instead of percent frequencies")
+                .required(false)
+                .id("raw-counts"),
+        );
+    with_chrom_sizes_args(with_mask_arg(with_chrom_filter_args(with_genome_args(
+        command,
+    ))))
+}
+
+fn build_cpg_cli() -> Command {
+    let command = Command::new(consts::CPG_CMD)
+        .author("Nathan LeRoy")
+        .about("Compute CpG count, density and observed/expected ratio for each region")
+        .arg(arg!(<path> "Path to bed file").required(true));
+    with_chrom_sizes_args(with_chrom_filter_args(with_genome_args(command)))
+}
+
+fn build_complexity_cli() -> Command {
+    let command = Command::new(consts::COMPLEXITY_CMD)
+        .author("Nathan LeRoy")
+        .about("Compute k-mer entropy, DUST score, longest homopolymer and dinucleotide-repeat fraction for each region")
+        .arg(arg!(<path> "Path to bed file").required(true))
+        .arg(
+            arg!(-k --k <K> "k-mer length used for the entropy (1-8)")
+                .required(false)
+                .default_value("2")
+                .value_parser(clap::value_parser!(usize)),
+        );
+    with_chrom_sizes_args(with_chrom_filter_args(with_genome_args(command)))
+}
+
+fn build_cpg_islands_cli() -> Command {
+    let command = Command::new(consts::CPG_ISLANDS_CMD)
+        .author("Nathan LeRoy")
+        .about("Call CpG islands across the genome, or only inside the regions of a bed file")
+        .arg(arg!([path] "Path to bed file of regions to search, defaults to the whole genome"))
+        .arg(
+            arg!(--criteria <CRITERIA> "Island criteria: gardiner-garden (200 bp, 50% GC, 0.6 o/e) or takai-jones (500 bp, 55% GC, 0.65 o/e)")
+                .required(false)
+                .default_value("gardiner-garden")
+                .value_parser(["gardiner-garden", "takai-jones"]),
+        )
+        .arg(
+            arg!(--"min-length" <LENGTH> "Override the minimum island length")
+                .required(false)
+                .id("min-length")
+                .value_parser(clap::value_parser!(u32)),
+        )
+        .arg(
+            arg!(--"min-gc" <GC> "Override the minimum GC fraction")
+                .required(false)
+                .id("min-gc")
+                .value_parser(clap::value_parser!(f64)),
+        )
+        .arg(
+            arg!(--"min-oe" <OE> "Override the minimum CpG observed/expected ratio")
+                .required(false)
+                .id("min-oe")
+                .value_parser(clap::value_parser!(f64)),
+        )
+        .arg(arg!(-o --output <OUTPUT> "Output bed file, defaults to stdout").required(false));
+    with_chrom_sizes_args(with_chrom_filter_args(with_genome_args(command)))
+}
+
+fn build_gaps_cli() -> Command {
+    let command = Command::new(consts::GAPS_CMD)
+        .author("Nathan LeRoy")
+        .about("Find assembly gaps (runs of N) in a genome and write them as a bed file")
+        .arg(
+            arg!(--"min-length" <LENGTH> "Minimum number of consecutive N bases")
+                .required(false)
+                .id("min-length")
+                .default_value("1")
+                .value_parser(clap::value_parser!(u32)),
+        )
+        .arg(arg!(-o --output <OUTPUT> "Output bed file, defaults to stdout").required(false));
+    with_chrom_filter_args(with_genome_args(command))
+}
+
+fn build_gc_ref_cli() -> Command {
+    let command = Command::new(consts::GC_REF_CMD)
+        .author("Nathan LeRoy")
+        .about("Compare the gc content of a query region set to the genome-wide expectation")
+        .arg(arg!(<path> "Path to bed file").required(true))
+        .arg(
+            arg!(--method <METHOD> "How to build the background: tile (windows of the median query width) or sample (windows of each query width)")
+                .required(false)
+                .default_value("tile")
+                .value_parser(["tile", "sample"]),
+        )
+        .arg(
+            arg!(--samples <SAMPLES> "Number of width-matched windows to sample")
+                .required(false)
+                .default_value("10000")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            arg!(--seed <SEED> "Seed for the random sampling")
+                .required(false)
+                .default_value("42")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            arg!(-u --"ignore-unused-chroms" "Skip regions on chromosomes missing from the genome (use --canonical to drop non-standard chromosomes)")
+                .required(false)
+                .num_args(0)
+                .visible_alias("ignore-unk-chroms")
+                .id("ignore-unk-chroms"),
+        )
+        .arg(
+            arg!(--"exclude-n" "Exclude N and other ambiguous bases from the denominator")
+                .required(false)
+                .id("exclude-n"),
+        )
+        .arg(
+            arg!(--background <BACKGROUND> "Write the background gc distribution to this file")
+                .required(false),
+        );
+    with_chrom_sizes_args(with_mask_arg(with_chrom_filter_args(with_genome_args(
+        command,
+    ))))
+}
+
+fn build_repeats_cli() -> Command {
+    let command = Command::new(consts::REPEATS_CMD)
+        .author("Nathan LeRoy")
+        .about("Compute the soft-masked (repeat) fraction of each region")
+        .arg(arg!(<path> "Path to bed file").required(true));
+    with_chrom_sizes_args(with_chrom_filter_args(with_genome_args(command)))
+}
+
+fn build_getfasta_cli() -> Command {
+    let command = Command::new(consts::GETFASTA_CMD)
+        .author("Nathan LeRoy")
+        .about("Write the sequence of each region as FASTA")
+        .arg(arg!(<path> "Path to bed file").required(true))
+        .arg(
+            arg!(--name "Name records by the BED name column instead of coordinates")
+                .required(false),
+        )
+        .arg(arg!(-s --strand "Reverse complement regions on the minus strand").required(false))
+        .arg(
+            arg!(--resize <WIDTH> "Resize every region to this width around its center first")
+                .required(false)
+                .value_parser(clap::value_parser!(u32)),
+        )
+        .arg(arg!(-o --output <OUTPUT> "Output FASTA file, defaults to stdout").required(false));
+    with_chrom_sizes_args(with_chrom_filter_args(with_genome_args(command)))
+}
+
+fn with_motif_args(command: Command) -> Command {
+    command
+        .arg(
+            arg!(-m --motifs <MOTIFS> "Motif file in JASPAR, MEME or HOMER format")
+                .required(true),
+        )
+        .arg(
+            arg!(--format <FORMAT> "Motif file format: jaspar, meme or homer (detected by default)")
+                .required(false),
+        )
+        .arg(
+            arg!(-p --"p-value" <P_VALUE> "p-value threshold for calling a motif hit")
+                .required(false)
+                .default_value("0.0001")
+                .value_parser(clap::value_parser!(f64))
+                .id("p-value"),
+        )
+}
+
+fn get_motifs(matches: &ArgMatches) -> Result<Vec<Motif>> {
+    let path = matches.get_one::<String>("motifs").unwrap();
+    let format = matches
+        .get_one::<String>("format")
+        .map(|format| format.parse::<MotifFormat>())
+        .transpose()?;
+    Motif::from_file(Path::new(path), format)
+        .with_context(|| format!("Failed to parse motifs: '{}'", path))
+}
+
+fn build_motifs_cli() -> Command {
+    let command = Command::new(consts::MOTIFS_CMD)
+        .author("Nathan LeRoy")
+        .about("Scan each region for motif occurrences on both strands")
+        .arg(arg!(<path> "Path to bed file").required(true))
+        .arg(arg!(--hits "Report every hit instead of per-region counts").required(false));
+    with_chrom_sizes_args(with_motif_args(with_chrom_filter_args(with_genome_args(
+        command,
+    ))))
+}
+
+fn build_motif_enrichment_cli() -> Command {
+    let command = Command::new(consts::MOTIF_ENRICHMENT_CMD)
+        .author("Nathan LeRoy")
+        .about("Test motifs for enrichment over a GC- and width-matched genomic background")
+        .arg(arg!(<path> "Path to bed file").required(true))
+        .arg(
+            arg!(--"background-ratio" <RATIO> "Background regions drawn per query region")
+                .required(false)
+                .default_value("2")
+                .value_parser(clap::value_parser!(usize))
+                .id("background-ratio"),
+        )
+        .arg(
+            arg!(--"gc-bin-width" <WIDTH> "Width of the GC content bins used for matching")
+                .required(false)
+                .default_value("0.05")
+                .value_parser(clap::value_parser!(f64))
+                .id("gc-bin-width"),
+        )
+        .arg(
+            arg!(--seed <SEED> "Seed for the background sampling")
+                .required(false)
+                .default_value("42")
+                .value_parser(clap::value_parser!(u64)),
+        );
+    with_chrom_sizes_args(with_motif_args(with_chrom_filter_args(with_genome_args(
+        command,
+    ))))
+}
+
+fn build_patterns_cli() -> Command {
+    let command = Command::new(consts::PATTERNS_CMD)
+        .author("Nathan LeRoy")
+        .about("Count IUPAC or regex pattern matches in each region")
+        .arg(arg!(<path> "Path to bed file").required(true))
+        .arg(
+            arg!(-p --pattern <PATTERN> "IUPAC string (e.g. CANNTG) or restricted regex (e.g. G{3,}[ACGT]{1,7}G{3,}), repeatable")
+                .required(true)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            arg!(--"plus-only" "Only search the plus strand")
+                .required(false)
+                .id("plus-only"),
+        )
+        .arg(
+            arg!(--hits "Report every match instead of per-region counts")
+                .required(false),
+        );
+    with_chrom_sizes_args(with_chrom_filter_args(with_genome_args(command)))
+}
+
+fn build_digest_cli() -> Command {
+    let command = Command::new(consts::DIGEST_CMD)
+        .author("Nathan LeRoy")
+        .about("Digest a genome in silico with restriction enzymes and map regions to the fragments")
+        .arg(arg!([path] "Path to bed file of regions to map to fragments"))
+        .arg(
+            arg!(-e --enzyme <ENZYME> "Enzyme name (e.g. DpnII, HindIII) or recognition site with the cut marked by ^ (e.g. A^AGCTT), repeatable")
+                .required(true)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            arg!(--lengths "Summarize fragment lengths instead of writing fragments or the region mapping")
+                .required(false),
+        )
+        .arg(
+            arg!(-o --output <OUTPUT> "Output bed file of fragments, defaults to stdout when no regions are given")
+                .required(false),
+        );
+    with_chrom_sizes_args(with_chrom_filter_args(with_genome_args(command)))
+}
+
+fn build_bed_digest_cli() -> Command {
+    let command = Command::new(consts::BED_DIGEST_CMD)
+        .author("Nathan LeRoy")
+        .about("Print a content digest of each bed file that ignores region order and whitespace")
+        .arg(arg!(<path> "Path to bed file").required(true).num_args(1..))
+        .arg(arg!(--names "Include region names in the digest").required(false));
+    with_chrom_filter_args(command)
+}
+
+fn build_genome_cli() -> Command {
+    Command::new(consts::GENOME_CMD)
+        .author("Nathan LeRoy")
+        .about("Manage genome assembly files")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new(consts::GENOME_PACK_CMD)
+                .author("Nathan LeRoy")
+                .about("Pack a FASTA genome into a file that keeps soft-masking and opens almost instantly")
+                .arg(arg!(<genome> "FASTA, .2bit or memory-mapped genome assembly file").required(true))
+                .arg(
+                    arg!(--format <FORMAT> "2bit: UCSC .2bit, compact but unpacked by each process. mmap: one byte per base, shared by every process through the page cache")
+                        .required(false)
+                        .default_value("2bit")
+                        .value_parser(["2bit", "mmap"]),
+                )
+                .arg(arg!(-o --output <OUTPUT> "Output genome file").required(true)),
+        )
+        .subcommand(
+            Command::new(consts::GENOME_DIGEST_CMD)
+                .author("Nathan LeRoy")
+                .about("Print the GA4GH sequence collection digests of a genome")
+                .arg(arg!(<genome> "FASTA, .2bit or memory-mapped genome assembly file").required(true))
+                .arg(
+                    arg!(--"per-chrom" "Print the name, length and refget digest of every chromosome instead")
+                        .required(false)
+                        .id("per-chrom"),
+                ),
+        )
+        .subcommand(
+            Command::new(consts::GENOME_COMPARE_CMD)
+                .author("Nathan LeRoy")
+                .about("Check whether two genomes, or a genome and a chrom sizes file, share a coordinate system")
+                .arg(arg!(<genome> "FASTA, .2bit or memory-mapped genome assembly file").required(true))
+                .arg(arg!(<other> "Second genome assembly file, or a chrom sizes file").required(true)),
+        )
+}
+
+fn write_length_summary<W: Write>(writer: &mut W, prefix: &str, lengths: &[u32]) -> Result<()> {
+    let summary = LengthSummary::from_lengths(lengths);
+
+    writeln!(writer, "{}_n\t{}", prefix, summary.n)?;
+    writeln!(writer, "{}_mean_length\t{:.4}", prefix, summary.mean)?;
+    writeln!(writer, "{}_median_length\t{:.4}", prefix, summary.median)?;
+    writeln!(
+        writer,
+        "{}_min_length\t{}",
+        prefix,
+        format_optional(summary.min.map(|l| l as f64))
+    )?;
+    writeln!(
+        writer,
+        "{}_max_length\t{}",
+        prefix,
+        format_optional(summary.max.map(|l| l as f64))
+    )?;
+    Ok(())
+}
+
+fn format_optional(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!("{:.4}", value),
+        None => "NA".to_string(),
+    }
 }
 
 fn build_parser() -> Command {
@@ -46,6 +624,22 @@ fn build_parser() -> Command {
         .subcommand_required(true)
         .subcommand(build_neighbor_distances_cli())
         .subcommand(build_gc_content_cli())
+        .subcommand(build_assembly_cli())
+        .subcommand(build_dinucl_freq_cli())
+        .subcommand(build_kmer_freq_cli())
+        .subcommand(build_cpg_cli())
+        .subcommand(build_gc_ref_cli())
+        .subcommand(build_repeats_cli())
+        .subcommand(build_getfasta_cli())
+        .subcommand(build_motifs_cli())
+        .subcommand(build_motif_enrichment_cli())
+        .subcommand(build_patterns_cli())
+        .subcommand(build_complexity_cli())
+        .subcommand(build_cpg_islands_cli())
+        .subcommand(build_gaps_cli())
+        .subcommand(build_digest_cli())
+        .subcommand(build_genome_cli())
+        .subcommand(build_bed_digest_cli())
 }
 
 fn main() -> Result<()> {
@@ -78,6 +672,11 @@ fn main() -> Result<()> {
                     )
                 })?
                 .into_sorted();
+            let rs = filter_chroms(rs, matches)?;
+
+            if let Some(chrom_sizes) = get_chrom_sizes(matches)? {
+                chrom_sizes.validate(&rs)?;
+            }
 
             let distances = calc_neighbor_distances(&rs)
                 .with_context(|| "Error calculating neighbor distances")?;
@@ -92,31 +691,640 @@ fn main() -> Result<()> {
 
         Some((consts::GC_CMD, matches)) => {
             // parse cli matches
-            let path_to_data = matches
-                .get_one::<String>("path")
-                .expect("Path to data is required.");
-            let genome = matches
-                .get_one::<String>("genome")
-                .expect("Please specify a genome assembly file");
-            let ignore_unused_chroms = matches.contains_id("ignore-unk-chroms");
+            let ignore_unused_chroms = matches.get_flag("ignore-unk-chroms");
 
             // parse given region set
-            let region_set = RegionSet::from_bed(Path::new(path_to_data))
-                .with_context(|| format!("Failed to parse bedfile: '{}'", path_to_data))?;
+            let region_set = get_region_set(matches)?;
 
             // read in the genome file
-            let genome = Path::new(genome);
-            let genome = GenomeAssembly::from_fasta(genome).with_context(|| {
-                format!("Error reading genome file: '{}'", genome.to_string_lossy())
-            })?;
+            let genome = get_genome(matches)?;
+
+            let options = GcContentOptions {
+                ignore_unk_chroms: ignore_unused_chroms,
+                exclude_ambiguous: matches.get_flag("exclude-n"),
+                mask: get_mask(matches)?,
+            };
+            let report_n_fraction = matches.get_flag("n-fraction");
 
             // compute gc content
-            let gc_content = calc_gc_content(&region_set, &genome, ignore_unused_chroms).unwrap();
+            let gc_stats = calc_gc_content_stats(&region_set, &genome, &options)
+                .with_context(|| "Error calculating gc content")?;
+
+            write_genome_digest(&mut handle, matches, &genome)?;
+
+            // dump to std-out, regions without informative bases are reported as NA
+            for stats in gc_stats {
+                let gc = format_optional(stats.gc_content);
+                match report_n_fraction {
+                    true => handle.write_all(
+                        format!("{}\t{}\n", gc, format_optional(stats.n_fraction)).as_bytes(),
+                    )?,
+                    false => handle.write_all(format!("{}\n", gc).as_bytes())?,
+                }
+            }
+
+            Ok(())
+        }
+
+        Some((consts::ASSEMBLY_CMD, matches)) => {
+            let region_set = read_region_set(matches)?;
+
+            let guess = detect_assembly(&region_set)?;
+
+            handle.write_all(format!("assembly\t{}\n", guess.assembly).as_bytes())?;
+            handle.write_all(format!("naming\t{}\n", guess.naming).as_bytes())?;
+            handle.write_all(format!("confidence\t{:.4}\n", guess.confidence).as_bytes())?;
+            handle.write_all(format!("unplaced\t{}\n", guess.n_unplaced).as_bytes())?;
+            for candidate in guess.candidates {
+                handle.write_all(
+                    format!(
+                        "candidate\t{}\t{}\t{}\t{:.4}\n",
+                        candidate.assembly,
+                        candidate.n_compatible,
+                        candidate.n_incompatible,
+                        candidate.probability
+                    )
+                    .as_bytes(),
+                )?;
+            }
+            for region in guess.incompatible_regions {
+                handle.write_all(
+                    format!(
+                        "incompatible\t{}\t{}\t{}\n",
+                        region.chr, region.start, region.end
+                    )
+                    .as_bytes(),
+                )?;
+            }
+
+            Ok(())
+        }
+
+        Some((consts::DINUCL_CMD, matches)) => {
+            let region_set = get_region_set(matches)?;
+            let genome = get_genome(matches)?;
+
+            let raw_counts = matches.get_flag("raw-counts");
+            write_genome_digest(&mut handle, matches, &genome)?;
+
+            if matches.get_flag("per-region") {
+                let matrix = calc_dinucl_freq_per_region(&region_set, &genome, raw_counts)
+                    .with_context(|| "Error calculating dinucleotide frequencies")?;
+                matrix.write_tsv(&mut handle)?;
+                return Ok(());
+            }
+
+            let counts = calc_dinucl_counts(&region_set, &genome)
+                .with_context(|| "Error calculating dinucleotide frequencies")?;
+            let total: u64 = counts.values().sum();
+
+            for dinucl in Dinucleotide::ALL {
+                let count = counts.get(&dinucl).copied().unwrap_or(0);
+                let value = match raw_counts {
+                    true => count as f64,
+                    false => count as f64 / total as f64 * 100.0,
+                };
+                handle.write_all(
+                    format!("{}\t{:.4}\n", dinucl.to_string()?.to_uppercase(), value).as_bytes(),
+                )?;
+            }
+
+            Ok(())
+        }
+
+        Some((consts::KMERS_CMD, matches)) => {
+            let region_set = get_region_set(matches)?;
+            let genome = get_genome(matches)?;
+
+            let k = *matches.get_one::<usize>("k").unwrap();
+            let canonical = matches.get_flag("fold-revcomp");
+            let raw_counts = matches.get_flag("raw-counts");
+            let mask = get_mask(matches)?;
+
+            if matches.get_flag("per-region") {
+                let matrix =
+                    calc_kmer_freq_per_region(&region_set, &genome, k, canonical, mask, raw_counts)
+                        .with_context(|| "Error calculating k-mer frequencies")?;
+                matrix.write_tsv(&mut handle)?;
+                return Ok(());
+            }
+
+            let kmer_counts = calc_kmer_counts(&region_set, &genome, k, canonical, mask)
+                .with_context(|| "Error calculating k-mer frequencies")?;
+            let total = kmer_counts.total() as f64;
+
+            for (kmer, count) in kmer_counts.iter() {
+                let value = match raw_counts {
+                    true => count as f64,
+                    false => count as f64 / total * 100.0,
+                };
+                handle.write_all(format!("{}\t{:.4}\n", kmer, value).as_bytes())?;
+            }
+
+            Ok(())
+        }
+
+        Some((consts::CPG_CMD, matches)) => {
+            let region_set = get_region_set(matches)?;
+            let genome = get_genome(matches)?;
+
+            let cpg_stats =
+                calc_cpg_oe(&region_set, &genome).with_context(|| "Error calculating CpG o/e")?;
+
+            handle.write_all(b"chr\tstart\tend\tcpg_count\tcpg_density\tcpg_oe\n")?;
+            for stats in cpg_stats {
+                handle.write_all(
+                    format!(
+                        "{}\t{}\t{}\t{}\t{:.4}\t{:.4}\n",
+                        stats.region.chr,
+                        stats.region.start,
+                        stats.region.end,
+                        stats.cpg_count,
+                        stats.cpg_density,
+                        stats.cpg_oe
+                    )
+                    .as_bytes(),
+                )?;
+            }
+
+            Ok(())
+        }
+
+        Some((consts::COMPLEXITY_CMD, matches)) => {
+            let region_set = get_region_set(matches)?;
+            let genome = get_genome(matches)?;
+
+            let k = *matches.get_one::<usize>("k").unwrap();
+            let complexity_stats = calc_complexity(&region_set, &genome, k)
+                .with_context(|| "Error calculating sequence complexity")?;
+
+            handle.write_all(
+                b"chr\tstart\tend\tentropy\tdust_score\tlongest_homopolymer\tdinucleotide_repeat_fraction\n",
+            )?;
+            for stats in complexity_stats {
+                handle.write_all(
+                    format!(
+                        "{}\t{}\t{}\t{:.4}\t{:.4}\t{}\t{:.4}\n",
+                        stats.region.chr,
+                        stats.region.start,
+                        stats.region.end,
+                        stats.entropy,
+                        stats.dust_score,
+                        stats.longest_homopolymer,
+                        stats.dinucleotide_repeat_fraction
+                    )
+                    .as_bytes(),
+                )?;
+            }
+
+            Ok(())
+        }
+
+        Some((consts::CPG_ISLANDS_CMD, matches)) => {
+            let genome = get_genome(matches)?;
+            // without a bed file, search every chromosome the filter flags keep
+            let region_set = match matches.contains_id("path") {
+                true => get_region_set(matches)?,
+                false => filter_chroms(
+                    RegionSet::from_regions(
+                        genome
+                            .chrom_lengths()
+                            .map(|(chr, size)| Region::new(chr, 0, size))
+                            .collect(),
+                    ),
+                    matches,
+                )?,
+            };
+
+            let mut criteria: CpgIslandCriteria =
+                matches.get_one::<String>("criteria").unwrap().parse()?;
+            if let Some(min_length) = matches.get_one::<u32>("min-length") {
+                criteria.min_length = *min_length;
+            }
+            if let Some(min_gc) = matches.get_one::<f64>("min-gc") {
+                criteria.min_gc = *min_gc;
+            }
+            if let Some(min_oe) = matches.get_one::<f64>("min-oe") {
+                criteria.min_oe = *min_oe;
+            }
+
+            let islands = find_cpg_islands(&genome, Some(&region_set), &criteria)
+                .with_context(|| "Error calling CpG islands")?;
+
+            match matches.get_one::<String>("output") {
+                Some(path) => {
+                    let mut file = std::io::BufWriter::new(
+                        std::fs::File::create(path)
+                            .with_context(|| format!("Failed to create '{}'", path))?,
+                    );
+                    islands.write_bed(&mut file)?;
+                    file.flush()?;
+                }
+                None => islands.write_bed(&mut handle)?,
+            }
+
+            Ok(())
+        }
+
+        Some((consts::GAPS_CMD, matches)) => {
+            let genome = get_genome(matches)?;
+
+            let min_length = *matches.get_one::<u32>("min-length").unwrap();
+            let gaps = find_gaps(&genome, min_length).with_context(|| "Error finding gaps")?;
+            let gaps = filter_chroms(gaps, matches)?;
+
+            match matches.get_one::<String>("output") {
+                Some(path) => {
+                    let mut file = std::io::BufWriter::new(
+                        std::fs::File::create(path)
+                            .with_context(|| format!("Failed to create '{}'", path))?,
+                    );
+                    gaps.write_bed(&mut file)?;
+                    file.flush()?;
+                }
+                None => gaps.write_bed(&mut handle)?,
+            }
+
+            Ok(())
+        }
+
+        Some((consts::DIGEST_CMD, matches)) => {
+            let region_set = match matches.contains_id("path") {
+                true => Some(get_region_set(matches)?),
+                false => None,
+            };
+            let genome = get_genome(matches)?;
+
+            let enzymes = matches
+                .get_many::<String>("enzyme")
+                .unwrap()
+                .map(|spec| RestrictionEnzyme::from_name_or_site(spec))
+                .collect::<Result<Vec<RestrictionEnzyme>>>()?;
+
+            let fragments = digest_genome(&genome, &enzymes, &get_chrom_classes(matches)?)
+                .with_context(|| "Error digesting genome")?;
+            let mapping = region_set
+                .as_ref()
+                .map(|region_set| map_to_fragments(region_set, &fragments));
+
+            if let Some(path) = matches.get_one::<String>("output") {
+                let mut file = std::io::BufWriter::new(
+                    std::fs::File::create(path)
+                        .with_context(|| format!("Failed to create '{}'", path))?,
+                );
+                fragments.write_bed(&mut file)?;
+                file.flush()?;
+            }
+
+            let lengths: Vec<u32> = fragments.iter().map(|fragment| fragment.width()).collect();
+
+            if matches.get_flag("lengths") {
+                write_length_summary(&mut handle, "fragments", &lengths)?;
+                if let Some(mapping) = &mapping {
+                    let mut mapped: Vec<usize> = mapping.iter().flatten().copied().collect();
+                    mapped.sort_unstable();
+                    mapped.dedup();
+                    let mapped_lengths: Vec<u32> = mapped.iter().map(|id| lengths[*id]).collect();
+                    write_length_summary(&mut handle, "mapped_fragments", &mapped_lengths)?;
+                }
+                return Ok(());
+            }
+
+            match (region_set, mapping) {
+                (Some(region_set), Some(mapping)) => {
+                    handle.write_all(
+                        b"chr\tstart\tend\tn_fragments\tfragment_ids\tfragment_lengths\n",
+                    )?;
+                    for (region, ids) in region_set.iter().zip(mapping) {
+                        let join = |values: Vec<String>| match values.is_empty() {
+                            true => "NA".to_string(),
+                            false => values.join(","),
+                        };
+                        handle.write_all(
+                            format!(
+                                "{}\t{}\t{}\t{}\t{}\t{}\n",
+                                region.chr,
+                                region.start,
+                                region.end,
+                                ids.len(),
+                                join(ids.iter().map(|id| id.to_string()).collect()),
+                                join(ids.iter().map(|id| lengths[*id].to_string()).collect())
+                            )
+                            .as_bytes(),
+                        )?;
+                    }
+                }
+                _ if !matches.contains_id("output") => fragments.write_bed(&mut handle)?,
+                _ => {}
+            }
+
+            Ok(())
+        }
+
+        Some((consts::GENOME_CMD, matches)) => match matches.subcommand() {
+            Some((consts::GENOME_PACK_CMD, matches)) => {
+                let genome_path = matches.get_one::<String>("genome").unwrap();
+                let path = matches.get_one::<String>("output").unwrap();
+                let format = matches
+                    .get_one::<String>("format")
+                    .unwrap()
+                    .parse::<GenomeFormat>()?;
+
+                let genome = GenomeAssembly::from_file(Path::new(genome_path))
+                    .with_context(|| format!("Error reading genome file: '{}'", genome_path))?;
+
+                pack_genome(&genome, Path::new(genome_path), Path::new(path), format)
+                    .with_context(|| format!("Error packing genome to '{}'", path))?;
+
+                Ok(())
+            }
+            Some((consts::GENOME_DIGEST_CMD, matches)) => {
+                let genome_path = matches.get_one::<String>("genome").unwrap();
+                let genome = GenomeAssembly::from_file(Path::new(genome_path))
+                    .with_context(|| format!("Error reading genome file: '{}'", genome_path))?;
+                let collection = SequenceCollection::from_genome(&genome)
+                    .with_context(|| "Error computing genome digests")?;
+
+                if matches.get_flag("per-chrom") {
+                    collection.write_tsv(&mut handle)?;
+                    return Ok(());
+                }
+
+                handle.write_all(format!("seqcol\t{}\n", collection.digest()).as_bytes())?;
+                handle.write_all(format!("names\t{}\n", collection.names_digest()).as_bytes())?;
+                handle
+                    .write_all(format!("lengths\t{}\n", collection.lengths_digest()).as_bytes())?;
+                handle.write_all(
+                    format!("sequences\t{}\n", collection.sequences_digest()).as_bytes(),
+                )?;
+                handle.write_all(
+                    format!(
+                        "sorted_name_length_pairs\t{}\n",
+                        collection.sorted_name_length_pairs_digest()
+                    )
+                    .as_bytes(),
+                )?;
+
+                Ok(())
+            }
+            Some((consts::GENOME_COMPARE_CMD, matches)) => {
+                let genome_path = matches.get_one::<String>("genome").unwrap();
+                let genome = GenomeAssembly::from_file(Path::new(genome_path))
+                    .with_context(|| format!("Error reading genome file: '{}'", genome_path))?;
+                let collection = SequenceCollection::from_genome(&genome)
+                    .with_context(|| "Error computing genome digests")?;
+
+                let other = matches.get_one::<String>("other").unwrap();
+                let comparison = match ChromSizes::is_chrom_sizes(Path::new(other))
+                    .with_context(|| format!("Error reading '{}'", other))?
+                {
+                    false => {
+                        let other_genome = GenomeAssembly::from_file(Path::new(other))
+                            .with_context(|| format!("Error reading genome file: '{}'", other))?;
+                        collection.compare(
+                            &SequenceCollection::from_genome(&other_genome)
+                                .with_context(|| "Error computing genome digests")?,
+                        )
+                    }
+                    true => collection.compare_sizes(
+                        &ChromSizes::from_file(Path::new(other)).with_context(|| {
+                            format!("Error reading chrom sizes file: '{}'", other)
+                        })?,
+                    ),
+                };
+
+                handle.write_all(
+                    format!("compatible\t{}\n", comparison.is_compatible()).as_bytes(),
+                )?;
+                handle.write_all(format!("identical\t{}\n", comparison.identical).as_bytes())?;
+                handle.write_all(format!("shared\t{}\n", comparison.shared.len()).as_bytes())?;
+                handle
+                    .write_all(format!("only_in_a\t{}\n", comparison.only_in_a.len()).as_bytes())?;
+                handle
+                    .write_all(format!("only_in_b\t{}\n", comparison.only_in_b.len()).as_bytes())?;
+                for name in comparison.length_mismatches {
+                    handle.write_all(format!("length_mismatch\t{}\n", name).as_bytes())?;
+                }
+                for name in comparison.sequence_mismatches {
+                    handle.write_all(format!("sequence_mismatch\t{}\n", name).as_bytes())?;
+                }
+
+                Ok(())
+            }
+            _ => unreachable!("Subcommand not found"),
+        },
+
+        Some((consts::GC_REF_CMD, matches)) => {
+            let region_set = get_region_set(matches)?;
+            let genome = get_genome(matches)?;
+
+            let method = match matches.get_one::<String>("method").unwrap().as_str() {
+                "sample" => GcBackgroundMethod::Sample {
+                    n_samples: *matches.get_one::<usize>("samples").unwrap(),
+                    seed: *matches.get_one::<u64>("seed").unwrap(),
+                },
+                _ => GcBackgroundMethod::Tile,
+            };
+            let options = GcContentOptions {
+                ignore_unk_chroms: matches.get_flag("ignore-unk-chroms"),
+                exclude_ambiguous: matches.get_flag("exclude-n"),
+                mask: get_mask(matches)?,
+            };
+
+            let gc_ref = calc_gc_content_ref(&region_set, &genome, method, &options)
+                .with_context(|| "Error calculating the gc content background")?;
+
+            if let Some(path) = matches.get_one::<String>("background") {
+                let mut file = std::fs::File::create(path)
+                    .with_context(|| format!("Failed to create '{}'", path))?;
+                for gc in gc_ref.background.iter() {
+                    file.write_all(format!("{:.4}\n", gc).as_bytes())?;
+                }
+            }
+
+            handle.write_all(format!("observed_n\t{}\n", gc_ref.observed.len()).as_bytes())?;
+            handle.write_all(format!("observed_mean\t{:.4}\n", gc_ref.observed_mean).as_bytes())?;
+            handle.write_all(format!("background_n\t{}\n", gc_ref.background.len()).as_bytes())?;
+            handle.write_all(
+                format!("background_mean\t{:.4}\n", gc_ref.background_mean).as_bytes(),
+            )?;
+            handle.write_all(
+                format!("mean_difference\t{:.4}\n", gc_ref.mean_difference).as_bytes(),
+            )?;
+            handle.write_all(format!("ks_statistic\t{:.4}\n", gc_ref.ks_statistic).as_bytes())?;
+
+            Ok(())
+        }
+
+        Some((consts::REPEATS_CMD, matches)) => {
+            let region_set = get_region_set(matches)?;
+            let genome = get_genome(matches)?;
+
+            let repeat_fractions = calc_repeat_fraction(&region_set, &genome)
+                .with_context(|| "Error calculating repeat fractions")?;
+
+            for (region, fraction) in region_set.iter().zip(repeat_fractions) {
+                handle.write_all(
+                    format!(
+                        "{}\t{}\t{}\t{}\n",
+                        region.chr,
+                        region.start,
+                        region.end,
+                        format_optional(fraction)
+                    )
+                    .as_bytes(),
+                )?;
+            }
+
+            Ok(())
+        }
+
+        Some((consts::GETFASTA_CMD, matches)) => {
+            let region_set = get_region_set(matches)?;
+            let genome = get_genome(matches)?;
+
+            let options = FastaOptions {
+                name_by: match matches.get_flag("name") {
+                    true => FastaName::Name,
+                    false => FastaName::Coords,
+                },
+                stranded: matches.get_flag("strand"),
+                resize: matches.get_one::<u32>("resize").copied(),
+            };
+
+            match matches.get_one::<String>("output") {
+                Some(path) => {
+                    let mut file = std::io::BufWriter::new(
+                        std::fs::File::create(path)
+                            .with_context(|| format!("Failed to create '{}'", path))?,
+                    );
+                    write_fasta(&region_set, &genome, &options, &mut file)?;
+                    file.flush()?;
+                }
+                None => write_fasta(&region_set, &genome, &options, &mut handle)?,
+            }
+
+            Ok(())
+        }
+
+        Some((consts::MOTIFS_CMD, matches)) => {
+            let region_set = get_region_set(matches)?;
+            let genome = get_genome(matches)?;
+
+            let motifs = get_motifs(matches)?;
+
+            let p_value = *matches.get_one::<f64>("p-value").unwrap();
+            let scan = scan_motifs(&region_set, &genome, &motifs, p_value)
+                .with_context(|| "Error scanning motifs")?;
+
+            if !matches.get_flag("hits") {
+                scan.counts.write_tsv(&mut handle)?;
+                return Ok(());
+            }
+
+            handle.write_all(b"chr\tstart\tend\tmotif\tstrand\tscore\tp_value\n")?;
+            for hit in scan.hits {
+                handle.write_all(
+                    format!(
+                        "{}\t{}\t{}\t{}\t{}\t{:.2}\t{:.3e}\n",
+                        hit.chr,
+                        hit.start,
+                        hit.end,
+                        scan.motifs[hit.motif_index],
+                        hit.strand,
+                        hit.score,
+                        hit.p_value
+                    )
+                    .as_bytes(),
+                )?;
+            }
+
+            Ok(())
+        }
+
+        Some((consts::MOTIF_ENRICHMENT_CMD, matches)) => {
+            let region_set = get_region_set(matches)?;
+            let genome = get_genome(matches)?;
+            let motifs = get_motifs(matches)?;
+
+            let options = MotifEnrichmentOptions {
+                p_value: *matches.get_one::<f64>("p-value").unwrap(),
+                background_ratio: *matches.get_one::<usize>("background-ratio").unwrap(),
+                gc_bin_width: *matches.get_one::<f64>("gc-bin-width").unwrap(),
+                seed: *matches.get_one::<u64>("seed").unwrap(),
+            };
+
+            let mut enrichment = calc_motif_enrichment(&region_set, &genome, &motifs, &options)
+                .with_context(|| "Error calculating motif enrichment")?;
+            enrichment.sort_by(|a, b| a.p_value.total_cmp(&b.p_value));
+
+            handle.write_all(
+                b"motif\tquery_hits\tquery_total\tbackground_hits\tbackground_total\tfold_enrichment\tp_value\tq_value\n",
+            )?;
+            for motif in enrichment {
+                handle.write_all(
+                    format!(
+                        "{}\t{}\t{}\t{}\t{}\t{:.4}\t{:.3e}\t{:.3e}\n",
+                        motif.motif,
+                        motif.query_hits,
+                        motif.query_total,
+                        motif.background_hits,
+                        motif.background_total,
+                        motif.fold_enrichment,
+                        motif.p_value,
+                        motif.q_value
+                    )
+                    .as_bytes(),
+                )?;
+            }
+
+            Ok(())
+        }
+
+        Some((consts::PATTERNS_CMD, matches)) => {
+            let region_set = get_region_set(matches)?;
+            let genome = get_genome(matches)?;
+
+            let patterns = matches
+                .get_many::<String>("pattern")
+                .unwrap()
+                .map(|pattern| Pattern::new(pattern))
+                .collect::<Result<Vec<Pattern>>>()?;
+            let both_strands = !matches.get_flag("plus-only");
+
+            let scan = search_patterns(&region_set, &genome, &patterns, both_strands)
+                .with_context(|| "Error searching patterns")?;
+
+            if !matches.get_flag("hits") {
+                scan.counts.write_tsv(&mut handle)?;
+                return Ok(());
+            }
+
+            handle.write_all(b"chr\tstart\tend\tpattern\tstrand\n")?;
+            for hit in scan.hits {
+                handle.write_all(
+                    format!(
+                        "{}\t{}\t{}\t{}\t{}\n",
+                        hit.chr, hit.start, hit.end, scan.patterns[hit.pattern_index], hit.strand
+                    )
+                    .as_bytes(),
+                )?;
+            }
+
+            Ok(())
+        }
+
+        Some((consts::BED_DIGEST_CMD, matches)) => {
+            let include_names = matches.get_flag("names");
 
-            // dump to std-out
-            // handle.write_all(format!("{:?}\n", gc_content).as_bytes())?;
-            for gc in gc_content {
-                handle.write_all(format!("{:.4}\n", gc).as_bytes())?;
+            // one line per file, like sha256sum
+            for path in matches.get_many::<String>("path").unwrap() {
+                let region_set = RegionSet::from_bed(Path::new(path))
+                    .with_context(|| format!("Failed to parse bedfile: '{}'", path))?;
+                let region_set = filter_chroms(region_set, matches)?;
+                handle.write_all(
+                    format!("{}\t{}\n", region_set.digest(include_names), path).as_bytes(),
+                )?;
             }
 
             Ok(())
@@ -0,0 +1,120 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+use memmap2::Mmap;
+
+use crate::models::{GenomeAssembly, Region};
+
+const MAGIC: &[u8; 8] = b"GDRSMMAP";
+const VERSION: u32 = 1;
+
+/// Writes every chromosome of `genome`, in file order, as one byte per base
+/// behind a small index, so the file can be memory-mapped and sliced without
+/// unpacking. Case and IUPAC codes are kept as they are.
+///
+/// Layout (little-endian): the magic `GDRSMMAP`, a u32 version and a u32
+/// sequence count, then per sequence a u32 name length, the name, a u64
+/// offset and a u64 length, then the sequences back to back.
+pub fn write_mapped<W: Write>(genome: &GenomeAssembly, writer: &mut W) -> Result<()> {
+    let chroms: Vec<(&String, u32)> = genome.chrom_lengths().collect();
+
+    let header_size: u64 = 16
+        + chroms
+            .iter()
+            .map(|(chr, _)| 20 + chr.len() as u64)
+            .sum::<u64>();
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&(chroms.len() as u32).to_le_bytes())?;
+
+    let mut offset = header_size;
+    for (chr, size) in chroms.iter() {
+        writer.write_all(&(chr.len() as u32).to_le_bytes())?;
+        writer.write_all(chr.as_bytes())?;
+        writer.write_all(&offset.to_le_bytes())?;
+        writer.write_all(&(*size as u64).to_le_bytes())?;
+        offset += *size as u64;
+    }
+
+    for (chr, size) in chroms {
+        writer.write_all(genome.seq_from_region(&Region::new(chr, 0, size))?)?;
+    }
+
+    Ok(())
+}
+
+/// True when `path` starts with the memory-mapped genome magic.
+pub fn is_mapped(path: &Path) -> Result<bool> {
+    let mut magic = [0u8; 8];
+    let mut file = File::open(path)?;
+    Ok(file.read_exact(&mut magic).is_ok() && &magic == MAGIC)
+}
+
+// sequence names and their byte ranges in the map
+type SeqRanges = Vec<(String, Range<usize>)>;
+
+/// Maps a file written by `write_mapped` and returns the map with the byte
+/// range of each sequence.
+pub(crate) fn open_mapped(path: &Path) -> Result<(Mmap, SeqRanges)> {
+    let file = File::open(path)?;
+    // safety: the map is read-only and the file is not expected to change
+    // while it is open
+    let mmap = unsafe { Mmap::map(&file)? };
+
+    let read_bytes = |pos: usize, len: usize| -> Result<&[u8]> {
+        pos.checked_add(len)
+            .and_then(|end| mmap.get(pos..end))
+            .ok_or_else(|| anyhow::anyhow!("Unexpected end of genome file at byte {}", pos))
+    };
+    let read_u32 =
+        |pos: usize| -> Result<u32> { Ok(u32::from_le_bytes(read_bytes(pos, 4)?.try_into()?)) };
+    let read_u64 =
+        |pos: usize| -> Result<u64> { Ok(u64::from_le_bytes(read_bytes(pos, 8)?.try_into()?)) };
+
+    ensure!(
+        read_bytes(0, 8)? == MAGIC,
+        "File is not a memory-mapped genome file"
+    );
+    let version = read_u32(8)?;
+    ensure!(
+        version == VERSION,
+        "Unsupported memory-mapped genome version {}, expected {}",
+        version,
+        VERSION
+    );
+
+    // the count comes from the file, so it only bounds the loop
+    let seq_count = read_u32(12)?;
+    let mut seqs = Vec::new();
+    let mut names = HashSet::new();
+    let mut pos = 16;
+    for _ in 0..seq_count {
+        let name_len = read_u32(pos)? as usize;
+        let name = String::from_utf8(read_bytes(pos + 4, name_len)?.to_vec())?;
+        pos += 4 + name_len;
+        ensure!(
+            names.insert(name.clone()),
+            "Duplicate chromosome found in memory-mapped genome file: {}",
+            name
+        );
+
+        let offset = read_u64(pos)? as usize;
+        let len = read_u64(pos + 8)? as usize;
+        pos += 16;
+
+        let end = offset
+            .checked_add(len)
+            .filter(|end| *end <= mmap.len())
+            .ok_or_else(|| {
+                anyhow::anyhow!("Sequence {} runs past the end of the genome file", name)
+            })?;
+        seqs.push((name, offset..end));
+    }
+
+    Ok((mmap, seqs))
+}
@@ -3,19 +3,89 @@ use std::ffi::OsStr;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufReader;
+use std::ops::Range;
 use std::path::Path;
+use std::sync::OnceLock;
 
 use anyhow::ensure;
 use anyhow::Result;
 use bio::io::fasta;
 use flate2::read::GzDecoder;
+use memmap2::Mmap;
 use rust_lapper::Lapper;
+use sha2::{Digest, Sha512};
+
+use crate::mapped::{is_mapped, open_mapped};
+use crate::refget::finish_sha512t24u;
+use crate::twobit::{is_twobit, TwoBitFile};
 
 #[derive(Eq, Clone)]
 pub struct Region {
     pub chr: String,
     pub start: u32,
     pub end: u32,
+    // BED name column, None if missing or "."
+    pub name: Option<String>,
+    pub strand: Strand,
+}
+
+impl Region {
+    pub fn new(chr: &str, start: u32, end: u32) -> Region {
+        Region {
+            chr: chr.to_string(),
+            start,
+            end,
+            name: None,
+            strand: Strand::Unknown,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// A copy of the region resized to `width` around its center, shifted to
+    /// stay on a chromosome of length `chrom_size`. The region is clipped
+    /// when the chromosome is shorter than `width`.
+    pub fn resize_center(&self, width: u32, chrom_size: u32) -> Region {
+        let center = self.start + self.width() / 2;
+        let start = center
+            .saturating_sub(width / 2)
+            .min(chrom_size.saturating_sub(width));
+        Region {
+            start,
+            end: start.saturating_add(width).min(chrom_size),
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strand {
+    Plus,
+    Minus,
+    #[default]
+    Unknown,
+}
+
+impl Strand {
+    pub fn from_bed(field: &str) -> Strand {
+        match field {
+            "+" => Strand::Plus,
+            "-" => Strand::Minus,
+            _ => Strand::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for Strand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Strand::Plus => write!(f, "+"),
+            Strand::Minus => write!(f, "-"),
+            Strand::Unknown => write!(f, "."),
+        }
+    }
 }
 
 impl PartialEq for Region {
@@ -25,11 +95,19 @@ impl PartialEq for Region {
 }
 
 pub struct RegionSet {
-    regions: HashMap<String, Vec<Region>>,
+    // regions in the order they were read
+    regions: Vec<Region>,
+    // chromosomes in order of first appearance
+    chroms: Vec<String>,
+    // chromosome -> positions in `regions`
+    index: HashMap<String, Vec<usize>>,
     sorted: bool,
 }
 
 impl RegionSet {
+    /// Read a BED file, optionally gzipped. Fields are tab separated; a line
+    /// without any tab is split on runs of whitespace instead. Blank lines and
+    /// `track`, `browser` and `#` header lines are skipped.
     pub fn from_bed(value: &Path) -> Result<RegionSet> {
         let is_gzipped = value.extension() == Some(OsStr::new("gz"));
         let file = File::open(value)?;
@@ -41,11 +119,29 @@ impl RegionSet {
 
         let reader = BufReader::new(file);
 
-        let mut regions = HashMap::new();
+        let mut regions = Vec::new();
 
         for line in reader.lines() {
             let line = line?;
-            let fields = line.split('\t').collect::<Vec<&str>>();
+            let line = line.trim_end();
+
+            // skip blank lines and headers
+            if line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with("track")
+                || line.starts_with("browser")
+            {
+                continue;
+            }
+
+            // fields are tab separated, or space separated when there are no tabs
+            let fields = match line.contains('\t') {
+                true => line
+                    .split('\t')
+                    .map(|field| field.trim())
+                    .collect::<Vec<&str>>(),
+                false => line.split_whitespace().collect::<Vec<&str>>(),
+            };
 
             ensure!(
                 fields.len() >= 3,
@@ -56,48 +152,190 @@ impl RegionSet {
             let start = fields[1].parse::<u32>()?;
             let end = fields[2].parse::<u32>()?;
 
+            ensure!(
+                start <= end,
+                "Invalid BED region {}:{}-{}, start is after end",
+                chr,
+                start,
+                end
+            );
+
+            let name = match fields.get(3) {
+                Some(name) if !name.is_empty() && *name != "." => Some(name.to_string()),
+                _ => None,
+            };
+            let strand = fields
+                .get(5)
+                .map(|strand| Strand::from_bed(strand))
+                .unwrap_or_default();
+
             let region = Region {
                 chr: chr.to_string(),
                 start,
                 end,
+                name,
+                strand,
             };
 
-            regions
-                .entry(chr.to_string())
-                .or_insert(Vec::new())
-                .push(region);
+            regions.push(region);
+        }
+
+        Ok(RegionSet::from_regions(regions))
+    }
+
+    pub fn from_regions(regions: Vec<Region>) -> RegionSet {
+        let mut chroms: Vec<String> = Vec::new();
+        let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+
+        for (i, region) in regions.iter().enumerate() {
+            match index.get_mut(&region.chr) {
+                Some(positions) => positions.push(i),
+                None => {
+                    chroms.push(region.chr.to_string());
+                    index.insert(region.chr.to_string(), vec![i]);
+                }
+            }
         }
 
-        Ok(RegionSet {
+        RegionSet {
             regions,
+            chroms,
+            index,
             sorted: false,
-        })
+        }
     }
 
     pub fn iter_chroms(&self) -> impl Iterator<Item = &String> {
-        self.regions.keys()
+        self.chroms.iter()
     }
 
     pub fn iter_regions(&self, chr: &str) -> impl Iterator<Item = &Region> {
-        self.regions.get(chr).unwrap().iter()
+        self.index
+            .get(chr)
+            .into_iter()
+            .flatten()
+            .map(|i| &self.regions[*i])
+    }
+
+    /// Iterate over every region in the order it was read in.
+    pub fn iter(&self) -> impl Iterator<Item = &Region> {
+        self.regions.iter()
     }
 
     pub fn into_sorted(self) -> RegionSet {
-        let mut regions = self.regions;
-        for region_vec in regions.values_mut() {
-            region_vec.sort_by(|a, b| a.start.cmp(&b.start));
+        let mut index = self.index;
+        for positions in index.values_mut() {
+            positions.sort_by_key(|i| self.regions[*i].start);
         }
 
         RegionSet {
-            regions,
+            regions: self.regions,
+            chroms: self.chroms,
+            index,
             sorted: true,
         }
     }
 
+    /// Rename every region to its canonical (UCSC) chromosome name.
+    pub fn into_canonical(self, aliases: &ChromAliases) -> RegionSet {
+        let n_chroms = self.chroms.len();
+        let regions = self
+            .regions
+            .into_iter()
+            .map(|region| Region {
+                chr: aliases.canonical(&region.chr),
+                ..region
+            })
+            .collect();
+
+        let region_set = RegionSet::from_regions(regions);
+
+        // keep the sort order unless two aliases of the same chromosome got merged
+        match self.sorted && region_set.chroms.len() == n_chroms {
+            true => region_set.into_sorted(),
+            false => region_set,
+        }
+    }
+
+    pub fn contains_chr(&self, chr: &str) -> bool {
+        self.index.contains_key(chr)
+    }
+
+    /// Keep only the regions on chromosomes whose class is in `keep`.
+    pub fn filter_chroms(self, keep: &[ChromClass]) -> RegionSet {
+        let regions = self
+            .regions
+            .into_iter()
+            .filter(|region| keep.contains(&ChromClass::from_chr(&region.chr)))
+            .collect();
+
+        let region_set = RegionSet::from_regions(regions);
+
+        match self.sorted {
+            true => region_set.into_sorted(),
+            false => region_set,
+        }
+    }
+
     pub fn is_sorted(&self) -> bool {
         self.sorted
     }
 
+    /// Write every region in input order as BED3, or as BED6 when any region
+    /// has a name or strand.
+    pub fn write_bed<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let bed6 = self
+            .regions
+            .iter()
+            .any(|region| region.name.is_some() || region.strand != Strand::Unknown);
+
+        for region in self.regions.iter() {
+            match bed6 {
+                true => writeln!(
+                    writer,
+                    "{}\t{}\t{}\t{}\t0\t{}",
+                    region.chr,
+                    region.start,
+                    region.end,
+                    region.name.as_deref().unwrap_or("."),
+                    region.strand
+                )?,
+                false => writeln!(writer, "{}\t{}\t{}", region.chr, region.start, region.end)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// A `sha512t24u` digest of the regions that ignores their order and the
+    /// layout of the file they came from. Every region is written as
+    /// `chr\tstart\tend\n`, with the name (or ".") as a fourth field when
+    /// `include_names` is set, and the sorted lines are hashed. Duplicate
+    /// regions are kept.
+    pub fn digest(&self, include_names: bool) -> String {
+        let mut keys: Vec<(&str, u32, u32, &str)> = self
+            .regions
+            .iter()
+            .map(|region| {
+                let name = match include_names {
+                    true => region.name.as_deref().unwrap_or("."),
+                    false => "",
+                };
+                (region.chr.as_str(), region.start, region.end, name)
+            })
+            .collect();
+        keys.sort_unstable();
+
+        let mut hasher = Sha512::new();
+        for (chr, start, end, name) in keys {
+            let line = match include_names {
+                true => format!("{}\t{}\t{}\t{}\n", chr, start, end, name),
+                false => format!("{}\t{}\t{}\n", chr, start, end),
+            };
+            hasher.update(line.as_bytes());
+        }
+        finish_sha512t24u(hasher)
+    }
+
     pub fn len(&self) -> usize {
         self.regions.len()
     }
@@ -107,8 +345,96 @@ impl RegionSet {
     }
 }
 
+/// A dense, row-major matrix with one row of statistics per region.
+pub struct RegionMatrix {
+    pub regions: Vec<Region>,
+    pub columns: Vec<String>,
+    values: Vec<f64>,
+}
+
+impl RegionMatrix {
+    pub fn new(columns: Vec<String>) -> RegionMatrix {
+        RegionMatrix {
+            regions: Vec::new(),
+            columns,
+            values: Vec::new(),
+        }
+    }
+
+    pub fn push_row(&mut self, region: &Region, row: &[f64]) -> Result<()> {
+        ensure!(
+            row.len() == self.columns.len(),
+            "Row has {} values but the matrix has {} columns",
+            row.len(),
+            self.columns.len()
+        );
+        self.regions.push(region.clone());
+        self.values.extend_from_slice(row);
+        Ok(())
+    }
+
+    pub fn row(&self, i: usize) -> &[f64] {
+        let n_cols = self.n_cols();
+        &self.values[i * n_cols..(i + 1) * n_cols]
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    pub fn n_rows(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn n_cols(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Write the matrix as a TSV with `chr`, `start` and `end` leading each row.
+    pub fn write_tsv<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writeln!(writer, "chr\tstart\tend\t{}", self.columns.join("\t"))?;
+        for (i, region) in self.regions.iter().enumerate() {
+            let row = self
+                .row(i)
+                .iter()
+                .map(|value| format!("{:.4}", value))
+                .collect::<Vec<String>>();
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{}",
+                region.chr,
+                region.start,
+                region.end,
+                row.join("\t")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// where the sequence of one chromosome lives
+enum ChromSeq {
+    // parsed from FASTA, or unpacked from .2bit the first time it is read
+    Loaded(OnceLock<Vec<u8>>),
+    // a byte range of the memory-mapped genome file
+    Mapped(Range<usize>),
+}
+
+// the file backing a genome, kept open for sequences that are read lazily
+enum GenomeSource {
+    Fasta,
+    TwoBit(TwoBitFile),
+    Mapped(Mmap),
+}
+
 pub struct GenomeAssembly {
-    seq_map: HashMap<String, Vec<u8>>,
+    seq_map: HashMap<String, ChromSeq>,
+    // chromosome names in file order
+    chroms: Vec<String>,
+    source: GenomeSource,
+    aliases: Option<ChromAliases>,
+    // canonical chromosome name -> name used in the FASTA
+    canonical_keys: HashMap<String, String>,
 }
 
 impl GenomeAssembly {
@@ -119,11 +445,15 @@ impl GenomeAssembly {
         let records = genome.records();
 
         // store the genome in a hashmap
-        let mut seq_map: HashMap<String, Vec<u8>> = HashMap::new();
+        let mut seq_map: HashMap<String, ChromSeq> = HashMap::new();
+        let mut chroms: Vec<String> = Vec::new();
         for record in records {
             match record {
                 Ok(record) => {
-                    seq_map.insert(record.id().to_string(), record.seq().to_owned());
+                    let seq = ChromSeq::Loaded(OnceLock::from(record.seq().to_owned()));
+                    if seq_map.insert(record.id().to_string(), seq).is_none() {
+                        chroms.push(record.id().to_string());
+                    }
                 }
                 Err(e) => {
                     return Err(anyhow::anyhow!("Error reading genome file: {}", e));
@@ -131,7 +461,130 @@ impl GenomeAssembly {
             }
         }
 
-        Ok(GenomeAssembly { seq_map })
+        Ok(GenomeAssembly {
+            seq_map,
+            chroms,
+            source: GenomeSource::Fasta,
+            aliases: None,
+            canonical_keys: HashMap::new(),
+        })
+    }
+
+    /// Open a UCSC .2bit file, such as one written by `gdrs genome pack`. Only
+    /// the index is read up front, each chromosome is unpacked the first time
+    /// it is used.
+    pub fn from_twobit(path: &Path) -> Result<GenomeAssembly> {
+        let twobit = TwoBitFile::open(path)
+            .map_err(|e| anyhow::anyhow!("Error reading .2bit file: {}", e))?;
+        let chroms: Vec<String> = twobit.sizes().map(|(chr, _)| chr.to_string()).collect();
+        let seq_map = chroms
+            .iter()
+            .map(|chr| (chr.to_string(), ChromSeq::Loaded(OnceLock::new())))
+            .collect();
+
+        Ok(GenomeAssembly {
+            seq_map,
+            chroms,
+            source: GenomeSource::TwoBit(twobit),
+            aliases: None,
+            canonical_keys: HashMap::new(),
+        })
+    }
+
+    /// Open a genome file written by `gdrs genome pack --format mmap`. Sequence
+    /// is read straight from the memory map, so every process and every
+    /// `GenomeAssembly` opened on the same file share one page-cache copy.
+    pub fn from_mapped(path: &Path) -> Result<GenomeAssembly> {
+        let (mmap, seqs) = open_mapped(path)
+            .map_err(|e| anyhow::anyhow!("Error reading memory-mapped genome file: {}", e))?;
+        let chroms: Vec<String> = seqs.iter().map(|(chr, _)| chr.to_string()).collect();
+        let seq_map = seqs
+            .into_iter()
+            .map(|(chr, range)| (chr, ChromSeq::Mapped(range)))
+            .collect();
+
+        Ok(GenomeAssembly {
+            seq_map,
+            chroms,
+            source: GenomeSource::Mapped(mmap),
+            aliases: None,
+            canonical_keys: HashMap::new(),
+        })
+    }
+
+    /// Open a memory-mapped genome, a .2bit file or a FASTA file, detected from
+    /// the file contents.
+    pub fn from_file(path: &Path) -> Result<GenomeAssembly> {
+        if is_mapped(path)? {
+            return GenomeAssembly::from_mapped(path);
+        }
+        match is_twobit(path)? {
+            true => GenomeAssembly::from_twobit(path),
+            false => GenomeAssembly::from_fasta(path),
+        }
+    }
+
+    /// Resolve chromosome names through `aliases` when they aren't found
+    /// verbatim, so a `chr1` region can be read from a FASTA that calls it `1`.
+    /// Fails when two chromosomes share a canonical name.
+    pub fn with_aliases(self, aliases: &ChromAliases) -> Result<GenomeAssembly> {
+        let canonical_keys = aliases.canonical_keys(self.chroms.iter())?;
+
+        Ok(GenomeAssembly {
+            seq_map: self.seq_map,
+            chroms: self.chroms,
+            source: self.source,
+            aliases: Some(aliases.clone()),
+            canonical_keys,
+        })
+    }
+
+    // the genome's own name for `chr`, resolving aliases
+    pub(crate) fn get_key<'a>(&'a self, chr: &'a str) -> Option<&'a str> {
+        if self.seq_map.contains_key(chr) {
+            return Some(chr);
+        }
+        let aliases = self.aliases.as_ref()?;
+        self.canonical_keys
+            .get(&aliases.canonical(chr))
+            .map(|key| key.as_str())
+    }
+
+    fn get_seq(&self, chr: &str) -> Result<Option<&[u8]>> {
+        let Some(key) = self.get_key(chr) else {
+            return Ok(None);
+        };
+
+        match (&self.seq_map[key], &self.source) {
+            (ChromSeq::Mapped(range), GenomeSource::Mapped(mmap)) => Ok(Some(&mmap[range.clone()])),
+            (ChromSeq::Loaded(cell), source) => {
+                if let Some(seq) = cell.get() {
+                    return Ok(Some(seq));
+                }
+                let GenomeSource::TwoBit(twobit) = source else {
+                    unreachable!("only .2bit sequences are loaded lazily");
+                };
+                let seq = twobit.read_seq(key)?;
+                Ok(Some(cell.get_or_init(|| seq)))
+            }
+            (ChromSeq::Mapped(_), _) => unreachable!("mapped sequences need a memory map"),
+        }
+    }
+
+    /// Chromosome names and lengths in file order, without unpacking any
+    /// sequence.
+    pub fn chrom_lengths(&self) -> impl Iterator<Item = (&String, u32)> {
+        let packed_sizes: HashMap<&String, u32> = match &self.source {
+            GenomeSource::TwoBit(twobit) => twobit.sizes().collect(),
+            _ => HashMap::new(),
+        };
+        self.chroms.iter().map(move |chr| match &self.seq_map[chr] {
+            ChromSeq::Loaded(cell) => match cell.get() {
+                Some(seq) => (chr, seq.len() as u32),
+                None => (chr, packed_sizes[chr]),
+            },
+            ChromSeq::Mapped(range) => (chr, range.len() as u32),
+        })
     }
 
     pub fn seq_from_region<'a>(&'a self, coords: &Region) -> Result<&'a [u8]> {
@@ -139,10 +592,18 @@ impl GenomeAssembly {
         let start = coords.start;
         let end = coords.end;
 
-        let seq = self.seq_map.get(chr);
+        let seq = self.get_seq(chr)?;
 
         match seq {
             Some(seq) => {
+                ensure!(
+                    start <= end && end as usize <= seq.len(),
+                    "Region {}:{}-{} is out of bounds for chromosome of length {}",
+                    chr,
+                    start,
+                    end,
+                    seq.len()
+                );
                 let seq = &seq[start as usize..end as usize];
                 Ok(seq)
             }
@@ -154,12 +615,540 @@ impl GenomeAssembly {
     }
 
     pub fn contains_chr(&self, chr: &str) -> bool {
-        self.seq_map.contains_key(chr)
+        self.get_key(chr).is_some()
+    }
+
+    /// Length of `chr`, without unpacking its sequence.
+    pub fn chrom_length(&self, chr: &str) -> Option<u32> {
+        let key = self.get_key(chr)?;
+        match (&self.seq_map[key], &self.source) {
+            (ChromSeq::Mapped(range), _) => Some(range.len() as u32),
+            (ChromSeq::Loaded(cell), source) => match (cell.get(), source) {
+                (Some(seq), _) => Some(seq.len() as u32),
+                (None, GenomeSource::TwoBit(twobit)) => twobit.size(key),
+                (None, _) => None,
+            },
+        }
     }
 
+    pub fn chrom_sizes(&self) -> ChromSizes {
+        ChromSizes::from(self)
+    }
 }
 
-#[derive(PartialEq, Eq, Hash)]
+/// Maps UCSC, Ensembl and RefSeq chromosome names onto a single canonical
+/// (UCSC-style) name. Names missing from the alias table fall back to a set of
+/// built-in rules covering `chr` prefixes, mitochondria and the human and mouse
+/// RefSeq accessions.
+#[derive(Clone, Default)]
+pub struct ChromAliases {
+    table: HashMap<String, String>,
+}
+
+impl ChromAliases {
+    pub fn builtin() -> ChromAliases {
+        ChromAliases::default()
+    }
+
+    /// Read a UCSC chromAlias file. Both the newer layout with a `# ucsc ...`
+    /// header (one row per chromosome, UCSC name first) and the legacy
+    /// `alias<TAB>chrom<TAB>source` layout are supported.
+    pub fn from_file(path: &Path) -> Result<ChromAliases> {
+        let is_gzipped = path.extension() == Some(OsStr::new("gz"));
+        let file = File::open(path)?;
+
+        let file: Box<dyn Read> = match is_gzipped {
+            true => Box::new(GzDecoder::new(file)),
+            false => Box::new(file),
+        };
+
+        let reader = BufReader::new(file);
+
+        let mut table = HashMap::new();
+        let mut has_header = false;
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if line.starts_with('#') {
+                has_header = has_header || i == 0;
+                continue;
+            }
+
+            let fields = line.split('\t').map(|f| f.trim()).collect::<Vec<&str>>();
+
+            ensure!(
+                fields.len() >= 2,
+                "Invalid chromAlias file format found. Each line needs at least two columns."
+            );
+
+            if has_header {
+                let canonical = fields[0];
+                for alias in fields.iter().filter(|alias| !alias.is_empty()) {
+                    table.insert(alias.to_string(), canonical.to_string());
+                }
+            } else {
+                table.insert(fields[0].to_string(), fields[1].to_string());
+                table.insert(fields[1].to_string(), fields[1].to_string());
+            }
+        }
+
+        Ok(ChromAliases { table })
+    }
+
+    pub fn canonical(&self, chr: &str) -> String {
+        match self.table.get(chr) {
+            Some(canonical) => canonical.to_string(),
+            None => builtin_canonical(chr),
+        }
+    }
+
+    pub fn same_chrom(&self, a: &str, b: &str) -> bool {
+        a == b || self.canonical(a) == self.canonical(b)
+    }
+
+    // canonical name -> chromosome name, for chromosomes of one file
+    fn canonical_keys<'a>(
+        &self,
+        chroms: impl Iterator<Item = &'a String>,
+    ) -> Result<HashMap<String, String>> {
+        let mut keys: HashMap<String, String> = HashMap::new();
+        for chr in chroms {
+            let canonical = self.canonical(chr);
+            if let Some(other) = keys.get(&canonical) {
+                return Err(anyhow::anyhow!(
+                    "Chromosomes {} and {} are both aliases of {}",
+                    other,
+                    chr,
+                    canonical
+                ));
+            }
+            keys.insert(canonical, chr.to_string());
+        }
+        Ok(keys)
+    }
+}
+
+fn builtin_canonical(chr: &str) -> String {
+    if let Some(accession) = chr.strip_prefix("NC_") {
+        let number = accession
+            .split('.')
+            .next()
+            .and_then(|n| n.parse::<u32>().ok());
+
+        // human (GRCh37/38) and mouse (GRCm38/39) primary assembly accessions
+        let name = match number {
+            Some(n @ 1..=22) => n.to_string(),
+            Some(23) => "X".to_string(),
+            Some(24) => "Y".to_string(),
+            Some(12920) => "M".to_string(),
+            Some(n @ 67..=85) => (n - 66).to_string(),
+            Some(86) => "X".to_string(),
+            Some(87) => "Y".to_string(),
+            Some(5089) => "M".to_string(),
+            _ => return chr.to_string(),
+        };
+        return format!("chr{}", name);
+    }
+
+    match chr {
+        "MT" | "Mito" | "MtDNA" | "chrMT" => "chrM".to_string(),
+        _ if chr.starts_with("chr") => chr.to_string(),
+        // leave other accessions and unplaced contigs alone
+        _ if chr.contains('_') || chr.contains('.') => chr.to_string(),
+        _ => format!("chr{}", chr),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChromClass {
+    Autosome,
+    Sex,
+    Mito,
+    Random,
+    Unplaced,
+    Alt,
+    Fix,
+    Decoy,
+    Ebv,
+    Other,
+}
+
+impl ChromClass {
+    pub const ALL: [ChromClass; 10] = [
+        ChromClass::Autosome,
+        ChromClass::Sex,
+        ChromClass::Mito,
+        ChromClass::Random,
+        ChromClass::Unplaced,
+        ChromClass::Alt,
+        ChromClass::Fix,
+        ChromClass::Decoy,
+        ChromClass::Ebv,
+        ChromClass::Other,
+    ];
+    pub const CANONICAL: [ChromClass; 3] =
+        [ChromClass::Autosome, ChromClass::Sex, ChromClass::Mito];
+
+    /// Classify a chromosome from its name. Ensembl and RefSeq names are
+    /// translated with the built-in aliases first. Note that `chrX` is always
+    /// treated as a sex chromosome, even for yeast where it is autosome ten.
+    pub fn from_chr(chr: &str) -> ChromClass {
+        let canonical = ChromAliases::builtin().canonical(chr);
+        let name = canonical.strip_prefix("chr").unwrap_or(&canonical);
+        let lower = name.to_ascii_lowercase();
+
+        if lower.ends_with("_decoy") || lower == "hs37d5" || name.starts_with("HLA-") {
+            ChromClass::Decoy
+        } else if lower.ends_with("_alt") {
+            ChromClass::Alt
+        } else if lower.ends_with("_fix") {
+            ChromClass::Fix
+        } else if lower.ends_with("_random") {
+            ChromClass::Random
+        } else if lower.starts_with("un_") || lower == "un" || canonical.contains('.') {
+            ChromClass::Unplaced
+        } else if lower == "ebv" {
+            ChromClass::Ebv
+        } else if name == "M" {
+            ChromClass::Mito
+        } else if matches!(name, "X" | "Y" | "W" | "Z") {
+            ChromClass::Sex
+        } else if is_autosome_name(name) {
+            ChromClass::Autosome
+        } else {
+            ChromClass::Other
+        }
+    }
+
+    pub fn is_canonical(&self) -> bool {
+        ChromClass::CANONICAL.contains(self)
+    }
+}
+
+// 1, 22, 2L/3R (drosophila arms) or roman numerals (yeast and worm)
+fn is_autosome_name(name: &str) -> bool {
+    let digits = name.trim_end_matches(['L', 'R']);
+    if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+        return true;
+    }
+    !name.is_empty() && name.chars().all(|c| matches!(c, 'I' | 'V' | 'X'))
+}
+
+impl std::str::FromStr for ChromClass {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<ChromClass> {
+        match s.to_ascii_lowercase().as_str() {
+            "autosome" | "autosomes" => Ok(ChromClass::Autosome),
+            "sex" => Ok(ChromClass::Sex),
+            "mito" | "chrm" | "mt" => Ok(ChromClass::Mito),
+            "random" => Ok(ChromClass::Random),
+            "unplaced" | "un" => Ok(ChromClass::Unplaced),
+            "alt" => Ok(ChromClass::Alt),
+            "fix" => Ok(ChromClass::Fix),
+            "decoy" => Ok(ChromClass::Decoy),
+            "ebv" => Ok(ChromClass::Ebv),
+            "other" => Ok(ChromClass::Other),
+            _ => Err(anyhow::anyhow!(
+                "Unknown chromosome class: '{}'. Expected one of: autosome, sex, mito, random, un, alt, fix, decoy, ebv, other",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ChromClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChromClass::Autosome => write!(f, "autosome"),
+            ChromClass::Sex => write!(f, "sex"),
+            ChromClass::Mito => write!(f, "mito"),
+            ChromClass::Random => write!(f, "random"),
+            ChromClass::Unplaced => write!(f, "un"),
+            ChromClass::Alt => write!(f, "alt"),
+            ChromClass::Fix => write!(f, "fix"),
+            ChromClass::Decoy => write!(f, "decoy"),
+            ChromClass::Ebv => write!(f, "ebv"),
+            ChromClass::Other => write!(f, "other"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChromNaming {
+    Ucsc,
+    Ensembl,
+    RefSeq,
+}
+
+impl ChromNaming {
+    pub fn from_chr(chr: &str) -> ChromNaming {
+        if chr.starts_with("chr") {
+            ChromNaming::Ucsc
+        } else if chr.starts_with("NC_") || chr.starts_with("NT_") || chr.starts_with("NW_") {
+            ChromNaming::RefSeq
+        } else {
+            ChromNaming::Ensembl
+        }
+    }
+}
+
+impl std::fmt::Display for ChromNaming {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChromNaming::Ucsc => write!(f, "UCSC"),
+            ChromNaming::Ensembl => write!(f, "Ensembl"),
+            ChromNaming::RefSeq => write!(f, "RefSeq"),
+        }
+    }
+}
+
+pub struct AssemblyCandidate {
+    pub assembly: String,
+    pub n_compatible: usize,
+    pub n_incompatible: usize,
+    pub probability: f64,
+}
+
+pub struct AssemblyGuess {
+    pub assembly: String,
+    pub naming: ChromNaming,
+    pub confidence: f64,
+    pub candidates: Vec<AssemblyCandidate>,
+    pub incompatible_regions: Vec<Region>,
+    pub n_unplaced: usize,
+}
+
+pub const BUILTIN_ASSEMBLIES: [&str; 7] =
+    ["hg38", "hg19", "mm39", "mm10", "dm6", "ce11", "sacCer3"];
+
+pub struct ChromSizes {
+    sizes: HashMap<String, u32>,
+}
+
+impl ChromSizes {
+    /// Read chromosome sizes from a UCSC `chrom.sizes` file or a
+    /// samtools `.fai` index. Both formats start with a chromosome name
+    /// and its length, any additional columns are ignored.
+    pub fn from_file(path: &Path) -> Result<ChromSizes> {
+        let is_gzipped = path.extension() == Some(OsStr::new("gz"));
+        let file = File::open(path)?;
+
+        let file: Box<dyn Read> = match is_gzipped {
+            true => Box::new(GzDecoder::new(file)),
+            false => Box::new(file),
+        };
+
+        ChromSizes::from_reader(BufReader::new(file))
+    }
+
+    /// True when the first line of `path` that isn't blank or a comment has a
+    /// name and an integer length, as in a chrom sizes or `.fai` file.
+    pub fn is_chrom_sizes(path: &Path) -> Result<bool> {
+        let is_gzipped = path.extension() == Some(OsStr::new("gz"));
+        let file = File::open(path)?;
+
+        let file: Box<dyn Read> = match is_gzipped {
+            true => Box::new(GzDecoder::new(file)),
+            false => Box::new(file),
+        };
+
+        for line in BufReader::new(file).split(b'\n') {
+            let Ok(line) = line else {
+                return Ok(false);
+            };
+            let line = String::from_utf8_lossy(&line);
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            return Ok(fields.len() >= 2 && fields[1].trim().parse::<u32>().is_ok());
+        }
+        Ok(false)
+    }
+
+    /// Load one of the chromosome size tables shipped with gdrs. These only
+    /// cover the primary assembly (no random, unplaced or alt contigs). UCSC
+    /// names (`hg38`) give `chr1`/`chrM`, GRC names (`GRCh38`) give the
+    /// Ensembl `1`/`MT`.
+    pub fn builtin(assembly: &str) -> Result<ChromSizes> {
+        let lower = assembly.to_ascii_lowercase();
+        let table = match lower.as_str() {
+            "hg38" | "grch38" => include_str!("../data/chrom_sizes/hg38.chrom.sizes"),
+            "hg19" | "grch37" => include_str!("../data/chrom_sizes/hg19.chrom.sizes"),
+            "mm39" | "grcm39" => include_str!("../data/chrom_sizes/mm39.chrom.sizes"),
+            "mm10" | "grcm38" => include_str!("../data/chrom_sizes/mm10.chrom.sizes"),
+            "dm6" => include_str!("../data/chrom_sizes/dm6.chrom.sizes"),
+            "ce11" => include_str!("../data/chrom_sizes/ce11.chrom.sizes"),
+            "saccer3" => include_str!("../data/chrom_sizes/sacCer3.chrom.sizes"),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Unknown assembly: '{}'. Available assemblies are: {}",
+                    assembly,
+                    BUILTIN_ASSEMBLIES.join(", ")
+                ))
+            }
+        };
+
+        let chrom_sizes = ChromSizes::from_reader(table.as_bytes())?;
+        if !lower.starts_with("grc") {
+            return Ok(chrom_sizes);
+        }
+
+        let sizes = chrom_sizes
+            .sizes
+            .into_iter()
+            .map(|(chr, size)| match chr.as_str() {
+                "chrM" => ("MT".to_string(), size),
+                _ => (chr.strip_prefix("chr").unwrap_or(&chr).to_string(), size),
+            })
+            .collect();
+        Ok(ChromSizes { sizes })
+    }
+
+    fn from_reader<R: BufRead>(reader: R) -> Result<ChromSizes> {
+        let mut sizes = HashMap::new();
+
+        for line in reader.lines() {
+            let line = line?;
+
+            // skip blank lines and comments
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields = line.split('\t').collect::<Vec<&str>>();
+
+            ensure!(
+                fields.len() >= 2,
+                "Invalid chrom sizes file format found. Each line needs a chromosome name and length."
+            );
+
+            let chr = fields[0].to_string();
+            let size = fields[1].trim().parse::<u32>()?;
+
+            ensure!(
+                !sizes.contains_key(&chr),
+                "Duplicate chromosome found in chrom sizes file: {}",
+                chr
+            );
+
+            sizes.insert(chr, size);
+        }
+
+        Ok(ChromSizes { sizes })
+    }
+
+    /// Compute chromosome sizes by reading a FASTA file one record at a
+    /// time, so only the largest chromosome is held in memory at once.
+    pub fn from_fasta(path: &Path) -> Result<ChromSizes> {
+        let file = File::open(path)?;
+        let genome = fasta::Reader::new(file);
+
+        let mut sizes = HashMap::new();
+        for record in genome.records() {
+            match record {
+                Ok(record) => {
+                    let chr = record.id().to_string();
+                    let size = u32::try_from(record.seq().len()).map_err(|_| {
+                        anyhow::anyhow!(
+                            "Chromosome {} is too long: {} bases",
+                            chr,
+                            record.seq().len()
+                        )
+                    })?;
+
+                    ensure!(
+                        !sizes.contains_key(&chr),
+                        "Duplicate chromosome found in FASTA file: {}",
+                        chr
+                    );
+
+                    sizes.insert(chr, size);
+                }
+                Err(e) => {
+                    return Err(anyhow::anyhow!("Error reading genome file: {}", e));
+                }
+            }
+        }
+
+        Ok(ChromSizes { sizes })
+    }
+
+    pub fn get(&self, chr: &str) -> Option<u32> {
+        self.sizes.get(chr).copied()
+    }
+
+    pub fn contains_chr(&self, chr: &str) -> bool {
+        self.sizes.contains_key(chr)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &u32)> {
+        self.sizes.iter()
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.sizes.values().map(|size| *size as u64).sum()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sizes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sizes.is_empty()
+    }
+    /// Collect every region that doesn't fit on this genome: regions on unknown
+    /// chromosomes, regions whose end runs past the chromosome, and regions
+    /// with start > end.
+    pub fn invalid_regions<'a>(&self, region_set: &'a RegionSet) -> Vec<&'a Region> {
+        let mut invalid = vec![];
+        for chr in region_set.iter_chroms() {
+            let size = self.get(chr);
+            for region in region_set.iter_regions(chr) {
+                match size {
+                    Some(size) if region.start <= region.end && region.end <= size => {}
+                    _ => invalid.push(region),
+                }
+            }
+        }
+        invalid
+    }
+
+    pub fn validate(&self, region_set: &RegionSet) -> Result<()> {
+        let invalid = self.invalid_regions(region_set);
+
+        if let Some(region) = invalid.first() {
+            return Err(anyhow::anyhow!(
+                "{} region(s) don't fit the provided chromosome sizes, first offender: {}:{}-{}",
+                invalid.len(),
+                region.chr,
+                region.start,
+                region.end
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&GenomeAssembly> for ChromSizes {
+    fn from(genome: &GenomeAssembly) -> Self {
+        let sizes = genome
+            .chrom_lengths()
+            .map(|(chr, size)| (chr.to_string(), size))
+            .collect();
+
+        ChromSizes { sizes }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Dinucleotide {
     Aa,
     Ac,
@@ -180,8 +1169,34 @@ pub enum Dinucleotide {
 }
 
 impl Dinucleotide {
+    pub const ALL: [Dinucleotide; 16] = [
+        Dinucleotide::Aa,
+        Dinucleotide::Ac,
+        Dinucleotide::Ag,
+        Dinucleotide::At,
+        Dinucleotide::Ca,
+        Dinucleotide::Cc,
+        Dinucleotide::Cg,
+        Dinucleotide::Ct,
+        Dinucleotide::Ga,
+        Dinucleotide::Gc,
+        Dinucleotide::Gg,
+        Dinucleotide::Gt,
+        Dinucleotide::Ta,
+        Dinucleotide::Tc,
+        Dinucleotide::Tg,
+        Dinucleotide::Tt,
+    ];
+
+    /// Match a pair of bases regardless of case (soft-masked repeats are
+    /// lowercase). Pairs containing N or any other IUPAC ambiguity code
+    /// return `None` and are left out of counts entirely.
     pub fn from_bytes(bytes: &[u8]) -> Option<Dinucleotide> {
-        match bytes {
+        if bytes.len() != 2 {
+            return None;
+        }
+        let bytes = [bytes[0].to_ascii_uppercase(), bytes[1].to_ascii_lowercase()];
+        match &bytes {
             b"Aa" => Some(Dinucleotide::Aa),
             b"Ac" => Some(Dinucleotide::Ac),
             b"Ag" => Some(Dinucleotide::Ag),
@@ -224,17 +1239,479 @@ impl Dinucleotide {
     }
 }
 
+/// Restricts sequence statistics to soft-masked (lowercase) or unmasked
+/// (uppercase) bases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MaskFilter {
+    #[default]
+    All,
+    Unmasked,
+    Masked,
+}
+
+impl MaskFilter {
+    pub fn accepts(&self, base: u8) -> bool {
+        match self {
+            MaskFilter::All => true,
+            MaskFilter::Unmasked => !base.is_ascii_lowercase(),
+            MaskFilter::Masked => base.is_ascii_lowercase(),
+        }
+    }
+}
+
+impl std::str::FromStr for MaskFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<MaskFilter> {
+        match s.to_ascii_lowercase().as_str() {
+            "all" => Ok(MaskFilter::All),
+            "unmasked" => Ok(MaskFilter::Unmasked),
+            "masked" => Ok(MaskFilter::Masked),
+            _ => Err(anyhow::anyhow!(
+                "Unknown mask filter: '{}'. Expected one of: all, unmasked, masked",
+                s
+            )),
+        }
+    }
+}
+
+/// File format written by `pack_genome`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GenomeFormat {
+    #[default]
+    TwoBit,
+    Mapped,
+}
+
+impl std::str::FromStr for GenomeFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<GenomeFormat> {
+        match s.to_ascii_lowercase().as_str() {
+            "2bit" => Ok(GenomeFormat::TwoBit),
+            "mmap" => Ok(GenomeFormat::Mapped),
+            _ => Err(anyhow::anyhow!(
+                "Unknown genome format: '{}'. Expected one of: 2bit, mmap",
+                s
+            )),
+        }
+    }
+}
+
+/// How `write_fasta` names its records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FastaName {
+    // chr:start-end
+    #[default]
+    Coords,
+    // the BED name column, falling back to coordinates
+    Name,
+}
+
+/// Options for `extract_sequences` and `write_fasta`.
+#[derive(Clone, Default)]
+pub struct FastaOptions {
+    pub name_by: FastaName,
+    // reverse complement regions on the minus strand
+    pub stranded: bool,
+    // resize every region to this width around its center first
+    pub resize: Option<u32>,
+}
+
+/// Options for `one_hot_encode`.
+#[derive(Clone, Default)]
+pub struct OneHotOptions {
+    // resize every region to this width around its center first
+    pub resize: Option<u32>,
+    // reverse complement regions on the minus strand
+    pub stranded: bool,
+    // append the reverse complement of every sequence after the forward ones
+    pub reverse_complement: bool,
+}
+
+/// One-hot encoded sequences returned by `one_hot_encode`: a flat, row-major
+/// (n_seqs, width, 4) array with channels in A, C, G, T order.
+pub struct OneHotSequences {
+    pub n_seqs: usize,
+    pub width: usize,
+    pub values: Vec<u8>,
+}
+
+/// Count and distribution of a set of lengths, such as fragment widths.
+/// Mean and median are NaN, and min and max None, when there are no lengths.
+#[derive(Debug, Clone)]
+pub struct LengthSummary {
+    pub n: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub min: Option<u32>,
+    pub max: Option<u32>,
+}
+
+impl LengthSummary {
+    pub fn from_lengths(lengths: &[u32]) -> LengthSummary {
+        let mut lengths = lengths.to_vec();
+        lengths.sort_unstable();
+
+        let mean = lengths.iter().map(|l| *l as f64).sum::<f64>() / lengths.len() as f64;
+        let median = match lengths.len() % 2 {
+            _ if lengths.is_empty() => f64::NAN,
+            0 => (lengths[lengths.len() / 2 - 1] as f64 + lengths[lengths.len() / 2] as f64) / 2.0,
+            _ => lengths[lengths.len() / 2] as f64,
+        };
+
+        LengthSummary {
+            n: lengths.len(),
+            mean,
+            median,
+            min: lengths.first().copied(),
+            max: lengths.last().copied(),
+        }
+    }
+}
+
+/// Options for `calc_gc_content_stats`.
+#[derive(Clone, Default)]
+pub struct GcContentOptions {
+    // skip regions on chromosomes missing from the genome
+    pub ignore_unk_chroms: bool,
+    // drop N and other ambiguous bases from the denominator
+    pub exclude_ambiguous: bool,
+    // only count soft-masked or unmasked bases
+    pub mask: MaskFilter,
+}
+
+/// Per-region GC summary returned by `calc_gc_content_stats`.
+#[derive(Clone)]
+pub struct GcStats {
+    pub region: Region,
+    // None when the region has no informative bases
+    pub gc_content: Option<f64>,
+    // fraction of N/ambiguous bases, None for zero-width regions
+    pub n_fraction: Option<f64>,
+}
+
+/// How `calc_gc_content_ref` builds the genome-wide GC background.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GcBackgroundMethod {
+    // non-overlapping windows the size of the median query width
+    Tile,
+    // windows with widths drawn from the query regions, placed at random
+    Sample { n_samples: usize, seed: u64 },
+}
+
+/// Expected GC distribution of a genome alongside the observed GC content of
+/// a region set.
+#[derive(Clone)]
+pub struct GcBackground {
+    pub observed: Vec<f64>,
+    pub background: Vec<f64>,
+    pub observed_mean: f64,
+    pub background_mean: f64,
+    // observed_mean - background_mean
+    pub mean_difference: f64,
+    // two-sample Kolmogorov-Smirnov statistic
+    pub ks_statistic: f64,
+}
+
+/// Per-region sequence complexity returned by `calc_complexity`.
+#[derive(Clone)]
+pub struct ComplexityStats {
+    pub region: Region,
+    // Shannon entropy of the k-mer spectrum in bits, NaN without any k-mers
+    pub entropy: f64,
+    // highest symmetric DUST score over 64 bp windows
+    pub dust_score: f64,
+    pub longest_homopolymer: u32,
+    // fraction of bases inside dinucleotide repeats such as (CA)n
+    pub dinucleotide_repeat_fraction: f64,
+}
+
+/// Thresholds a CpG island must meet over its whole length.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CpgIslandCriteria {
+    pub min_length: u32,
+    // (C + G) / length
+    pub min_gc: f64,
+    // observed/expected CpG ratio, CpG * length / (C * G)
+    pub min_oe: f64,
+}
+
+impl CpgIslandCriteria {
+    /// Gardiner-Garden and Frommer (1987): at least 200 bp, 50% GC and 0.6 o/e.
+    pub fn gardiner_garden() -> CpgIslandCriteria {
+        CpgIslandCriteria {
+            min_length: 200,
+            min_gc: 0.5,
+            min_oe: 0.6,
+        }
+    }
+
+    /// Takai and Jones (2002): at least 500 bp, 55% GC and 0.65 o/e.
+    pub fn takai_jones() -> CpgIslandCriteria {
+        CpgIslandCriteria {
+            min_length: 500,
+            min_gc: 0.55,
+            min_oe: 0.65,
+        }
+    }
+}
+
+impl Default for CpgIslandCriteria {
+    fn default() -> Self {
+        CpgIslandCriteria::gardiner_garden()
+    }
+}
+
+impl std::str::FromStr for CpgIslandCriteria {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "gardiner-garden" => Ok(CpgIslandCriteria::gardiner_garden()),
+            "takai-jones" => Ok(CpgIslandCriteria::takai_jones()),
+            _ => Err(anyhow::anyhow!(
+                "Unknown CpG island criteria '{}'. Expected one of: gardiner-garden, takai-jones",
+                s
+            )),
+        }
+    }
+}
+
+/// Per-region CpG summary returned by `calc_cpg_oe`.
+#[derive(Clone)]
+pub struct CpgStats {
+    pub region: Region,
+    pub cpg_count: u32,
+    // CpGs per base pair
+    pub cpg_density: f64,
+    // Gardiner-Garden observed/expected ratio: CpG * length / (C * G)
+    pub cpg_oe: f64,
+}
+
+pub const MAX_KMER_K: usize = 8;
+
+// 2-bit encoding: A=0, C=1, G=2, T=3, anything else can't be encoded
+fn encode_base(base: u8) -> Option<u32> {
+    match base {
+        b'A' | b'a' => Some(0),
+        b'C' | b'c' => Some(1),
+        b'G' | b'g' => Some(2),
+        b'T' | b't' => Some(3),
+        _ => None,
+    }
+}
+
+/// Reverse complement of a sequence, keeping case and complementing the IUPAC
+/// ambiguity codes.
+pub fn reverse_complement(seq: &[u8]) -> Vec<u8> {
+    seq.iter()
+        .rev()
+        .map(|base| {
+            let complement = match base.to_ascii_uppercase() {
+                b'A' => b'T',
+                b'C' => b'G',
+                b'G' => b'C',
+                b'T' | b'U' => b'A',
+                b'R' => b'Y',
+                b'Y' => b'R',
+                b'K' => b'M',
+                b'M' => b'K',
+                b'B' => b'V',
+                b'V' => b'B',
+                b'D' => b'H',
+                b'H' => b'D',
+                other => other,
+            };
+            match base.is_ascii_lowercase() {
+                true => complement.to_ascii_lowercase(),
+                false => complement,
+            }
+        })
+        .collect()
+}
+
+pub fn encode_kmer(kmer: &[u8]) -> Option<u32> {
+    kmer.iter()
+        .try_fold(0, |code, base| Some((code << 2) | encode_base(*base)?))
+}
+
+pub fn decode_kmer(code: u32, k: usize) -> String {
+    (0..k)
+        .rev()
+        .map(|i| match (code >> (2 * i)) & 3 {
+            0 => 'A',
+            1 => 'C',
+            2 => 'G',
+            _ => 'T',
+        })
+        .collect()
+}
+
+pub fn revcomp_kmer(code: u32, k: usize) -> u32 {
+    let mut rc = 0;
+    for i in 0..k {
+        rc = (rc << 2) | (3 - ((code >> (2 * i)) & 3));
+    }
+    rc
+}
+
+/// Counts of every k-mer in a dense table indexed by its 2-bit code. K-mers
+/// spanning N or any other ambiguity code are skipped. When `canonical` is set,
+/// each k-mer is counted under the lexicographically smaller of itself and its
+/// reverse complement.
+#[derive(Clone)]
+pub struct KmerCounts {
+    k: usize,
+    canonical: bool,
+    counts: Vec<u64>,
+    // codes of the reported columns, only canonical ones when `canonical` is set
+    columns: Vec<u32>,
+}
+
+impl KmerCounts {
+    pub fn new(k: usize, canonical: bool) -> Result<KmerCounts> {
+        ensure!(
+            (1..=MAX_KMER_K).contains(&k),
+            "k must be between 1 and {}, got {}",
+            MAX_KMER_K,
+            k
+        );
+
+        let n_codes = 1u32 << (2 * k);
+        let columns = (0..n_codes)
+            .filter(|code| !canonical || *code <= revcomp_kmer(*code, k))
+            .collect();
+
+        Ok(KmerCounts {
+            k,
+            canonical,
+            counts: vec![0; n_codes as usize],
+            columns,
+        })
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    pub fn count_seq(&mut self, seq: &[u8]) {
+        self.count_seq_masked(seq, MaskFilter::All)
+    }
+
+    /// Counts k-mers made up only of bases accepted by `mask`, so k-mers never
+    /// span a masked/unmasked boundary.
+    pub fn count_seq_masked(&mut self, seq: &[u8], mask: MaskFilter) {
+        let k = self.k;
+        let bits = (1u32 << (2 * k)) - 1;
+
+        let mut fwd: u32 = 0;
+        let mut rev: u32 = 0;
+        let mut valid = 0;
+
+        for base in seq {
+            if !mask.accepts(*base) {
+                valid = 0;
+                continue;
+            }
+
+            match encode_base(*base) {
+                Some(code) => {
+                    fwd = ((fwd << 2) | code) & bits;
+                    rev = (rev >> 2) | ((3 - code) << (2 * (k - 1)));
+                    valid += 1;
+                }
+                None => {
+                    valid = 0;
+                    continue;
+                }
+            }
+
+            if valid >= k {
+                let code = match self.canonical {
+                    true => fwd.min(rev),
+                    false => fwd,
+                };
+                self.counts[code as usize] += 1;
+            }
+        }
+    }
+
+    /// The 2-bit codes that make up the columns of this table, in order.
+    pub fn codes(&self) -> impl Iterator<Item = u32> + '_ {
+        self.columns.iter().copied()
+    }
+
+    pub fn kmers(&self) -> Vec<String> {
+        self.codes().map(|code| decode_kmer(code, self.k)).collect()
+    }
+
+    pub fn get(&self, kmer: &str) -> Option<u64> {
+        if kmer.len() != self.k {
+            return None;
+        }
+        let code = encode_kmer(kmer.as_bytes())?;
+        let code = match self.canonical {
+            true => code.min(revcomp_kmer(code, self.k)),
+            false => code,
+        };
+        Some(self.counts[code as usize])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (String, u64)> + '_ {
+        self.codes()
+            .map(|code| (decode_kmer(code, self.k), self.counts[code as usize]))
+    }
+
+    pub fn values(&self) -> Vec<u64> {
+        self.codes()
+            .map(|code| self.counts[code as usize])
+            .collect()
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.iter_mut().for_each(|count| *count = 0);
+    }
+}
+
 pub struct TSSIndex {
     tree: HashMap<String, Lapper<u32, Vec<Region>>>,
+    aliases: Option<ChromAliases>,
+    canonical_keys: HashMap<String, String>,
 }
 
 impl TSSIndex {
+    /// Fails when two chromosomes share a canonical name.
+    pub fn with_aliases(self, aliases: &ChromAliases) -> Result<TSSIndex> {
+        let mut chroms: Vec<&String> = self.tree.keys().collect();
+        chroms.sort();
+        let canonical_keys = aliases.canonical_keys(chroms.into_iter())?;
+
+        Ok(TSSIndex {
+            tree: self.tree,
+            aliases: Some(aliases.clone()),
+            canonical_keys,
+        })
+    }
+
+    fn get_tree(&self, chr: &str) -> Option<&Lapper<u32, Vec<Region>>> {
+        self.tree.get(chr).or_else(|| {
+            let aliases = self.aliases.as_ref()?;
+            let key = self.canonical_keys.get(&aliases.canonical(chr))?;
+            self.tree.get(key)
+        })
+    }
+
     pub fn has_chr(&self, chr: &str) -> bool {
-        self.tree.contains_key(chr)
+        self.get_tree(chr).is_some()
     }
     pub fn query(&self, region: &Region) -> Option<Vec<&Region>> {
         let chr = &region.chr;
-        let chr_tree = self.tree.get(chr);
+        let chr_tree = self.get_tree(chr);
 
         match chr_tree {
             None => None, // our index doesnt have that chromosome they gave us
@@ -0,0 +1,564 @@
+use std::fs::read_to_string;
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+
+use crate::models::{RegionMatrix, Strand};
+
+// log-odds scores are kept as integers in units of 1/100 bit, so that
+// the score distribution can be computed exactly
+const SCORE_SCALE: f64 = 100.0;
+
+// added to every base frequency so that unseen bases don't score -inf
+const PSEUDO_FREQ: f64 = 0.01;
+
+// A, C, G, T frequencies used when a motif file doesn't give any
+const UNIFORM_BACKGROUND: [f64; 4] = [0.25; 4];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MotifFormat {
+    Jaspar,
+    Meme,
+    Homer,
+}
+
+impl MotifFormat {
+    /// Guesses the format from the contents of a motif file.
+    pub fn detect(contents: &str) -> MotifFormat {
+        let lines = || contents.lines().map(|line| line.trim());
+
+        if lines().any(|line| line.starts_with("MEME version") || line.starts_with("MOTIF")) {
+            return MotifFormat::Meme;
+        }
+
+        // JASPAR rows are labelled with their base, e.g. "A [ 4 19 0 ]"
+        let labelled = lines().any(|line| {
+            let mut chars = line.chars();
+            matches!(chars.next(), Some('A' | 'C' | 'G' | 'T'))
+                && matches!(chars.next(), Some(' ' | '\t' | '['))
+        });
+        if labelled {
+            return MotifFormat::Jaspar;
+        }
+
+        // HOMER headers are tab separated, JASPAR headers use spaces
+        match lines().find(|line| line.starts_with('>')) {
+            Some(header) if header.contains('\t') => MotifFormat::Homer,
+            _ => MotifFormat::Jaspar,
+        }
+    }
+}
+
+impl std::str::FromStr for MotifFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<MotifFormat> {
+        match s.to_ascii_lowercase().as_str() {
+            "jaspar" => Ok(MotifFormat::Jaspar),
+            "meme" => Ok(MotifFormat::Meme),
+            "homer" => Ok(MotifFormat::Homer),
+            _ => Err(anyhow::anyhow!(
+                "Unknown motif format: '{}'. Expected one of: jaspar, meme, homer",
+                s
+            )),
+        }
+    }
+}
+
+/// A position frequency matrix with one row of A, C, G, T frequencies per
+/// motif position, and the background frequencies it is scored against.
+#[derive(Debug, Clone)]
+pub struct Motif {
+    pub id: String,
+    pub name: Option<String>,
+    freqs: Vec<[f64; 4]>,
+    background: [f64; 4],
+}
+
+impl Motif {
+    /// Builds a motif from per-position A, C, G, T counts or probabilities.
+    pub fn new(id: &str, name: Option<String>, rows: Vec<[f64; 4]>) -> Result<Motif> {
+        ensure!(!rows.is_empty(), "Motif '{}' has no positions", id);
+
+        let mut freqs = Vec::with_capacity(rows.len());
+        for (i, row) in rows.iter().enumerate() {
+            let total: f64 = row.iter().sum();
+            ensure!(
+                row.iter().all(|value| *value >= 0.0 && value.is_finite()) && total > 0.0,
+                "Motif '{}' has an invalid row at position {}",
+                id,
+                i + 1
+            );
+            freqs.push(row.map(|value| value / total));
+        }
+
+        Ok(Motif {
+            id: id.to_string(),
+            name,
+            freqs,
+            background: UNIFORM_BACKGROUND,
+        })
+    }
+
+    /// Score the motif against these A, C, G, T background frequencies
+    /// instead of a uniform background.
+    pub fn with_background(self, background: [f64; 4]) -> Result<Motif> {
+        let total: f64 = background.iter().sum();
+        ensure!(
+            background
+                .iter()
+                .all(|freq| *freq > 0.0 && freq.is_finite()),
+            "Background frequencies of motif '{}' must all be positive",
+            self.id
+        );
+        Ok(Motif {
+            background: background.map(|freq| freq / total),
+            ..self
+        })
+    }
+
+    pub fn from_file(path: &Path, format: Option<MotifFormat>) -> Result<Vec<Motif>> {
+        let contents = read_to_string(path)?;
+        let motifs = Motif::parse(&contents, format)?;
+        ensure!(
+            !motifs.is_empty(),
+            "No motifs found in '{}'",
+            path.display()
+        );
+        Ok(motifs)
+    }
+
+    pub fn parse(contents: &str, format: Option<MotifFormat>) -> Result<Vec<Motif>> {
+        match format.unwrap_or_else(|| MotifFormat::detect(contents)) {
+            MotifFormat::Jaspar => parse_jaspar(contents),
+            MotifFormat::Meme => parse_meme(contents),
+            MotifFormat::Homer => parse_homer(contents),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.freqs.len()
+    }
+
+    pub fn freqs(&self) -> &[[f64; 4]] {
+        &self.freqs
+    }
+
+    pub fn background(&self) -> &[f64; 4] {
+        &self.background
+    }
+
+    /// Most likely base at each position.
+    pub fn consensus(&self) -> String {
+        self.freqs
+            .iter()
+            .map(|row| {
+                let best = (0..4).fold(0, |best, i| if row[i] > row[best] { i } else { best });
+                ['A', 'C', 'G', 'T'][best]
+            })
+            .collect()
+    }
+}
+
+fn parse_numbers(line: &str) -> Result<Vec<f64>> {
+    line.split(|c: char| c.is_whitespace() || c == '[' || c == ']')
+        .filter(|field| !field.is_empty())
+        .map(|field| {
+            field
+                .parse::<f64>()
+                .map_err(|_| anyhow::anyhow!("Invalid motif value: '{}'", field))
+        })
+        .collect()
+}
+
+// >MA0004.1 Arnt, followed by four rows of counts with optional "A [ ... ]" labels
+fn parse_jaspar(contents: &str) -> Result<Vec<Motif>> {
+    let mut motifs = Vec::new();
+    let mut header: Option<&str> = None;
+    let mut rows: Vec<(Option<char>, Vec<f64>)> = Vec::new();
+
+    let mut finish = |header: Option<&str>, rows: &mut Vec<(Option<char>, Vec<f64>)>| {
+        if let Some(header) = header {
+            motifs.push(jaspar_motif(header, rows)?);
+        }
+        rows.clear();
+        Ok::<(), anyhow::Error>(())
+    };
+
+    for line in contents.lines().map(|line| line.trim()) {
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(stripped) = line.strip_prefix('>') {
+            finish(header, &mut rows)?;
+            header = Some(stripped.trim());
+            continue;
+        }
+
+        let label = line
+            .chars()
+            .next()
+            .filter(|c| matches!(c, 'A' | 'C' | 'G' | 'T'));
+        let values = match label {
+            Some(_) => parse_numbers(&line[1..])?,
+            None => parse_numbers(line)?,
+        };
+        rows.push((label, values));
+    }
+    finish(header, &mut rows)?;
+
+    Ok(motifs)
+}
+
+fn jaspar_motif(header: &str, rows: &[(Option<char>, Vec<f64>)]) -> Result<Motif> {
+    let mut fields = header.split_whitespace();
+    let id = fields.next().unwrap_or("motif");
+    let name = fields.next().map(|name| name.to_string());
+
+    ensure!(
+        rows.len() == 4,
+        "JASPAR motif '{}' must have four rows, found {}",
+        id,
+        rows.len()
+    );
+
+    let width = rows[0].1.len();
+    ensure!(
+        rows.iter().all(|(_, values)| values.len() == width),
+        "JASPAR motif '{}' has rows of different lengths",
+        id
+    );
+
+    let mut columns = vec![[0.0; 4]; width];
+    for (i, (label, values)) in rows.iter().enumerate() {
+        let base = match label {
+            Some(label) => "ACGT".find(*label).unwrap(),
+            None => i,
+        };
+        for (position, value) in values.iter().enumerate() {
+            columns[position][base] = *value;
+        }
+    }
+
+    Motif::new(id, name, columns)
+}
+
+// MOTIF id [alt], followed by a "letter-probability matrix:" block. The
+// "Background letter frequencies" block applies to every motif in the file.
+fn parse_meme(contents: &str) -> Result<Vec<Motif>> {
+    let mut motifs = Vec::new();
+    let mut lines = contents.lines().map(|line| line.trim()).peekable();
+    let mut current: Option<(String, Option<String>)> = None;
+    let mut background = UNIFORM_BACKGROUND;
+
+    while let Some(line) = lines.next() {
+        if line.starts_with("Background letter frequencies") {
+            // "A 0.303 C 0.183 G 0.209 T 0.306", possibly over several lines
+            let mut freqs: [Option<f64>; 4] = [None; 4];
+            while freqs.iter().any(|freq| freq.is_none()) {
+                let line = lines
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("Truncated MEME background frequencies"))?;
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                // is_multiple_of needs a newer toolchain than the crate otherwise does
+                #[allow(clippy::manual_is_multiple_of)]
+                let is_pairs = fields.len() % 2 == 0;
+                ensure!(
+                    !fields.is_empty() && is_pairs,
+                    "Invalid MEME background frequencies: '{}'",
+                    line
+                );
+                for pair in fields.chunks(2) {
+                    let base = "ACGT"
+                        .find(pair[0])
+                        .filter(|_| pair[0].len() == 1)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("Unsupported background letter '{}'", pair[0])
+                        })?;
+                    freqs[base] = Some(pair[1].parse::<f64>().map_err(|_| {
+                        anyhow::anyhow!("Invalid background frequency: '{}'", pair[1])
+                    })?);
+                }
+            }
+            background = freqs.map(|freq| freq.unwrap());
+            continue;
+        }
+
+        if let Some(stripped) = line.strip_prefix("MOTIF") {
+            let mut fields = stripped.split_whitespace();
+            let id = fields
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("MEME motif is missing an identifier"))?;
+            current = Some((id.to_string(), fields.next().map(|name| name.to_string())));
+            continue;
+        }
+
+        if line.starts_with("letter-probability matrix") {
+            let (id, name) = current
+                .take()
+                .ok_or_else(|| anyhow::anyhow!("MEME matrix found before a MOTIF line"))?;
+
+            let mut rows = Vec::new();
+            while let Some(row) = lines.peek() {
+                match parse_numbers(row) {
+                    Ok(values) if values.len() == 4 => {
+                        rows.push([values[0], values[1], values[2], values[3]]);
+                        lines.next();
+                    }
+                    _ => break,
+                }
+            }
+
+            motifs.push(Motif::new(&id, name, rows)?.with_background(background)?);
+        }
+    }
+
+    Ok(motifs)
+}
+
+// >consensus<TAB>name<TAB>threshold..., followed by one row of A, C, G, T probabilities per position
+fn parse_homer(contents: &str) -> Result<Vec<Motif>> {
+    let mut motifs = Vec::new();
+    let mut current: Option<(String, Option<String>)> = None;
+    let mut rows: Vec<[f64; 4]> = Vec::new();
+
+    for line in contents.lines().map(|line| line.trim()) {
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(stripped) = line.strip_prefix('>') {
+            if let Some((id, name)) = current.take() {
+                motifs.push(Motif::new(&id, name, std::mem::take(&mut rows))?);
+            }
+
+            let fields: Vec<&str> = stripped.split('\t').collect();
+            let consensus = fields[0].trim();
+            current = match fields.get(1) {
+                Some(name) => Some((name.trim().to_string(), Some(consensus.to_string()))),
+                None => Some((consensus.to_string(), None)),
+            };
+            continue;
+        }
+
+        let values = parse_numbers(line)?;
+        ensure!(
+            values.len() == 4,
+            "HOMER motif rows must have four values, found: '{}'",
+            line
+        );
+        rows.push([values[0], values[1], values[2], values[3]]);
+    }
+    if let Some((id, name)) = current {
+        motifs.push(Motif::new(&id, name, rows)?);
+    }
+
+    Ok(motifs)
+}
+
+/// Log-odds scoring matrix of a motif against its background, with the exact
+/// distribution of scores under that background for p-value lookups.
+pub struct Pwm {
+    scores: Vec<[i32; 4]>,
+    min_score: i32,
+    // tail[s - min_score] = P(score >= s)
+    tail: Vec<f64>,
+}
+
+impl Pwm {
+    pub fn new(motif: &Motif) -> Pwm {
+        let background = motif.background();
+        let scores: Vec<[i32; 4]> = motif
+            .freqs()
+            .iter()
+            .map(|row| {
+                let mut scores = [0; 4];
+                for base in 0..4 {
+                    let freq = (row[base] + PSEUDO_FREQ) / (1.0 + 4.0 * PSEUDO_FREQ);
+                    scores[base] = ((freq / background[base]).log2() * SCORE_SCALE).round() as i32;
+                }
+                scores
+            })
+            .collect();
+
+        let min_score: i32 = scores.iter().map(|row| row.iter().min().unwrap()).sum();
+        let max_score: i32 = scores.iter().map(|row| row.iter().max().unwrap()).sum();
+
+        // distribution of the summed score, one position at a time
+        let mut dist = vec![0.0; (max_score - min_score + 1) as usize];
+        dist[0] = 1.0;
+        let mut offset = 0;
+        let mut span = 0;
+        for row in scores.iter() {
+            let row_min = *row.iter().min().unwrap();
+            let row_max = *row.iter().max().unwrap();
+            let mut next = vec![0.0; dist.len()];
+            for (s, p) in dist.iter().enumerate().take(span + 1) {
+                if *p == 0.0 {
+                    continue;
+                }
+                for base in 0..4 {
+                    next[s + (row[base] - row_min) as usize] += p * background[base];
+                }
+            }
+            dist = next;
+            offset += row_min;
+            span += (row_max - row_min) as usize;
+        }
+        debug_assert_eq!(offset, min_score);
+
+        let mut tail = vec![0.0; dist.len()];
+        let mut acc = 0.0;
+        for i in (0..dist.len()).rev() {
+            acc += dist[i];
+            tail[i] = acc;
+        }
+
+        Pwm {
+            scores,
+            min_score,
+            tail,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn max_score(&self) -> i32 {
+        self.min_score + self.tail.len() as i32 - 1
+    }
+
+    /// Lowest score whose p-value is at most `p_value`. When even the best
+    /// possible match is less significant, the result is above `max_score`.
+    pub fn threshold(&self, p_value: f64) -> i32 {
+        match self.tail.iter().position(|tail| *tail <= p_value) {
+            Some(i) => self.min_score + i as i32,
+            None => self.max_score() + 1,
+        }
+    }
+
+    pub fn p_value(&self, score: i32) -> f64 {
+        match score {
+            s if s <= self.min_score => 1.0,
+            s if s > self.max_score() => 0.0,
+            s => self.tail[(s - self.min_score) as usize],
+        }
+    }
+
+    /// Scores in bits.
+    pub fn to_bits(score: i32) -> f64 {
+        score as f64 / SCORE_SCALE
+    }
+
+    /// Every window of `seq` scoring at least `threshold` on either strand as
+    /// (offset, strand, score). Both strands of a window are reported, except
+    /// for a palindromic window, which is the same site on either strand and
+    /// is reported once on the plus strand. Windows containing N or other
+    /// ambiguity codes are skipped.
+    pub fn scan(&self, seq: &[u8], threshold: i32) -> Vec<(usize, Strand, i32)> {
+        let width = self.width();
+        let mut hits = Vec::new();
+        if seq.len() < width {
+            return hits;
+        }
+
+        let codes: Vec<Option<usize>> = seq
+            .iter()
+            .map(|base| match base.to_ascii_uppercase() {
+                b'A' => Some(0),
+                b'C' => Some(1),
+                b'G' => Some(2),
+                b'T' => Some(3),
+                _ => None,
+            })
+            .collect();
+
+        'windows: for offset in 0..=(seq.len() - width) {
+            let mut fwd = 0;
+            let mut rev = 0;
+            // the window reads the same on the minus strand
+            let mut palindrome = true;
+            for j in 0..width {
+                let (Some(f), Some(r)) = (codes[offset + j], codes[offset + width - 1 - j]) else {
+                    continue 'windows;
+                };
+                fwd += self.scores[j][f];
+                rev += self.scores[j][3 - r];
+                palindrome &= f == 3 - r;
+            }
+
+            if fwd >= threshold {
+                hits.push((offset, Strand::Plus, fwd));
+            }
+            if rev >= threshold && !palindrome {
+                hits.push((offset, Strand::Minus, rev));
+            }
+        }
+
+        hits
+    }
+}
+
+/// A motif occurrence in genomic coordinates.
+#[derive(Debug, Clone)]
+pub struct MotifHit {
+    // index into the scanned RegionSet, in input order
+    pub region_index: usize,
+    // index into `MotifScan::motifs`
+    pub motif_index: usize,
+    pub chr: String,
+    pub start: u32,
+    pub end: u32,
+    pub strand: Strand,
+    // log-odds score in bits
+    pub score: f64,
+    pub p_value: f64,
+}
+
+/// Output of `scan_motifs`: hit counts per region and motif, and every hit.
+pub struct MotifScan {
+    pub motifs: Vec<String>,
+    pub counts: RegionMatrix,
+    pub hits: Vec<MotifHit>,
+}
+
+/// Options for `calc_motif_enrichment`.
+#[derive(Debug, Clone)]
+pub struct MotifEnrichmentOptions {
+    // p-value threshold for calling a motif hit
+    pub p_value: f64,
+    // background regions drawn per query region
+    pub background_ratio: usize,
+    // width of the GC content bins that background regions are matched on
+    pub gc_bin_width: f64,
+    pub seed: u64,
+}
+
+impl Default for MotifEnrichmentOptions {
+    fn default() -> Self {
+        MotifEnrichmentOptions {
+            p_value: 1e-4,
+            background_ratio: 2,
+            gc_bin_width: 0.05,
+            seed: 42,
+        }
+    }
+}
+
+/// Enrichment of a motif in a region set over its GC-matched background,
+/// counting regions with at least one hit.
+#[derive(Debug, Clone)]
+pub struct MotifEnrichment {
+    pub motif: String,
+    pub query_hits: usize,
+    pub query_total: usize,
+    pub background_hits: usize,
+    pub background_total: usize,
+    // query hit fraction over background hit fraction, infinite when the
+    // motif is missing from the background and NaN when it is missing from both
+    pub fold_enrichment: f64,
+    // one-sided Fisher's exact (hypergeometric) test
+    pub p_value: f64,
+    // Benjamini-Hochberg adjusted p-value
+    pub q_value: f64,
+}
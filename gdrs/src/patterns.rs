@@ -0,0 +1,147 @@
+use anyhow::{ensure, Result};
+use regex::bytes::{Regex, RegexBuilder};
+
+use crate::models::{RegionMatrix, Strand};
+
+// characters allowed in a pattern besides the IUPAC codes
+const REGEX_SYNTAX: &str = "[]{}()|*+?,^0123456789";
+
+pub(crate) fn iupac_bases(code: u8) -> Option<&'static str> {
+    match code {
+        b'A' => Some("A"),
+        b'C' => Some("C"),
+        b'G' => Some("G"),
+        b'T' | b'U' => Some("T"),
+        b'R' => Some("AG"),
+        b'Y' => Some("CT"),
+        b'S' => Some("CG"),
+        b'W' => Some("AT"),
+        b'K' => Some("GT"),
+        b'M' => Some("AC"),
+        b'B' => Some("CGT"),
+        b'D' => Some("AGT"),
+        b'H' => Some("ACT"),
+        b'V' => Some("ACG"),
+        b'N' => Some("ACGT"),
+        _ => None,
+    }
+}
+
+/// A sequence pattern: an IUPAC string such as `CANNTG`, or a restricted
+/// regex over IUPAC codes using only classes, groups, alternation and
+/// quantifiers, such as `G{3,}[ACGT]{1,7}G{3,}`. Matching ignores case and
+/// degenerate codes never match N in the sequence. IUPAC strings report
+/// overlapping matches, regexes report non-overlapping ones.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pub name: String,
+    regex: Regex,
+    overlapping: bool,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Pattern> {
+        let spec = pattern.trim().to_ascii_uppercase();
+        ensure!(!spec.is_empty(), "Pattern must not be empty");
+
+        let mut translated = String::with_capacity(spec.len() * 4);
+        let mut in_class = false;
+        for c in spec.bytes() {
+            match (c, iupac_bases(c)) {
+                (_, Some(bases)) if in_class => translated.push_str(bases),
+                (_, Some(bases)) if bases.len() == 1 => translated.push_str(bases),
+                (_, Some(bases)) => translated.push_str(&format!("[{}]", bases)),
+                (b'[', None) if !in_class => {
+                    in_class = true;
+                    translated.push('[');
+                }
+                (b']', None) if in_class => {
+                    in_class = false;
+                    translated.push(']');
+                }
+                (b'^', None) if in_class && translated.ends_with('[') => translated.push('^'),
+                (c, None) if REGEX_SYNTAX.contains(c as char) && !in_class && c != b'^' => {
+                    translated.push(c as char)
+                }
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "Invalid character '{}' in pattern '{}'. Patterns may only use IUPAC codes, classes, groups, alternation and quantifiers",
+                        c as char,
+                        pattern
+                    ))
+                }
+            }
+        }
+        ensure!(
+            !in_class,
+            "Unclosed character class in pattern '{}'",
+            pattern
+        );
+
+        // negated classes must not match N or other non-ACGT bytes
+        let translated = translated.replace("[^", "[^NRYSWKMBDHV");
+
+        let regex = RegexBuilder::new(&translated)
+            .case_insensitive(true)
+            .unicode(false)
+            .build()
+            .map_err(|e| anyhow::anyhow!("Invalid pattern '{}': {}", pattern, e))?;
+
+        ensure!(
+            !regex.is_match(b""),
+            "Pattern '{}' matches the empty sequence",
+            pattern
+        );
+
+        Ok(Pattern {
+            name: pattern.trim().to_string(),
+            regex,
+            overlapping: spec.bytes().all(|c| iupac_bases(c).is_some()),
+        })
+    }
+
+    /// Start and end of every match in `seq`.
+    pub fn find_all(&self, seq: &[u8]) -> Vec<(usize, usize)> {
+        if !self.overlapping {
+            return self
+                .regex
+                .find_iter(seq)
+                .map(|m| (m.start(), m.end()))
+                .collect();
+        }
+
+        let mut matches = Vec::new();
+        let mut offset = 0;
+        while offset < seq.len() {
+            match self.regex.find_at(seq, offset) {
+                Some(m) => {
+                    matches.push((m.start(), m.end()));
+                    offset = m.start() + 1;
+                }
+                None => break,
+            }
+        }
+        matches
+    }
+}
+
+/// A pattern match in genomic coordinates.
+#[derive(Debug, Clone)]
+pub struct PatternHit {
+    // index into the searched RegionSet, in input order
+    pub region_index: usize,
+    // index into `PatternScan::patterns`
+    pub pattern_index: usize,
+    pub chr: String,
+    pub start: u32,
+    pub end: u32,
+    pub strand: Strand,
+}
+
+/// Output of `search_patterns`: match counts per region and pattern, and
+/// every match.
+pub struct PatternScan {
+    pub patterns: Vec<String>,
+    pub counts: RegionMatrix,
+    pub hits: Vec<PatternHit>,
+}
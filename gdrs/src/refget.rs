@@ -0,0 +1,295 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Result};
+use base64::engine::general_purpose::URL_SAFE;
+use base64::Engine;
+use sha2::{Digest, Sha512};
+
+use crate::models::{ChromSizes, GenomeAssembly, Region};
+
+// bases uppercased and hashed at a time, so a chromosome is never copied whole
+const DIGEST_CHUNK: usize = 1 << 16;
+
+// extension of the digest file written next to a packed genome
+const DIGESTS_EXTENSION: &str = "seqcol";
+
+/// The GA4GH `sha512t24u` digest: the first 24 bytes of the SHA-512 hash,
+/// base64url encoded.
+pub fn sha512t24u(bytes: &[u8]) -> String {
+    finish_sha512t24u(Sha512::new_with_prefix(bytes))
+}
+
+// the sha512t24u digest of everything fed to `hasher`
+pub(crate) fn finish_sha512t24u(hasher: Sha512) -> String {
+    URL_SAFE.encode(&hasher.finalize()[..24])
+}
+
+/// The GA4GH refget digest of a sequence, `SQ.` followed by the `sha512t24u`
+/// of the uppercased sequence.
+pub fn refget_digest(seq: &[u8]) -> String {
+    let mut hasher = Sha512::new();
+    let mut chunk = Vec::with_capacity(DIGEST_CHUNK);
+    for bases in seq.chunks(DIGEST_CHUNK) {
+        chunk.clear();
+        chunk.extend(bases.iter().map(|base| base.to_ascii_uppercase()));
+        hasher.update(&chunk);
+    }
+    format!("SQ.{}", finish_sha512t24u(hasher))
+}
+
+// a JSON string literal, escaped as RFC 8785 canonical JSON requires
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\u{08}' => escaped.push_str("\\b"),
+            '\u{0c}' => escaped.push_str("\\f"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+// a canonical JSON array of already serialized items
+fn json_array(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<String>>().join(","))
+}
+
+/// A GA4GH sequence collection: the names, lengths and refget digests of
+/// every sequence of an assembly, in file order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceCollection {
+    pub names: Vec<String>,
+    pub lengths: Vec<u32>,
+    pub sequences: Vec<String>,
+}
+
+impl SequenceCollection {
+    /// Digest every chromosome of `genome`. This reads the whole genome.
+    pub fn from_genome(genome: &GenomeAssembly) -> Result<SequenceCollection> {
+        let mut collection = SequenceCollection {
+            names: Vec::new(),
+            lengths: Vec::new(),
+            sequences: Vec::new(),
+        };
+        for (chr, size) in genome.chrom_lengths() {
+            let seq = genome.seq_from_region(&Region::new(chr, 0, size))?;
+            collection.names.push(chr.to_string());
+            collection.lengths.push(size);
+            collection.sequences.push(refget_digest(seq));
+        }
+        Ok(collection)
+    }
+
+    /// Where the digests of the genome packed at `path` are written.
+    pub fn digests_path(path: &Path) -> PathBuf {
+        let mut digests = path.as_os_str().to_owned();
+        digests.push(".");
+        digests.push(DIGESTS_EXTENSION);
+        PathBuf::from(digests)
+    }
+
+    /// Write the digests next to the genome at `path` as `<path>.seqcol`.
+    /// This is a record for the user, gdrs never reads it back in place of
+    /// hashing the genome.
+    pub fn write_digests(&self, path: &Path) -> Result<()> {
+        let mut file = BufWriter::new(File::create(SequenceCollection::digests_path(path))?);
+        self.write_tsv(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Write one `name\tlength\tdigest` line per sequence.
+    pub fn write_tsv<W: Write>(&self, writer: &mut W) -> Result<()> {
+        for ((name, length), seq) in self
+            .names
+            .iter()
+            .zip(self.lengths.iter())
+            .zip(self.sequences.iter())
+        {
+            writeln!(writer, "{}\t{}\t{}", name, length, seq)?;
+        }
+        Ok(())
+    }
+
+    /// Read a collection written by `write_tsv`.
+    pub fn from_tsv(path: &Path) -> Result<SequenceCollection> {
+        let mut collection = SequenceCollection {
+            names: Vec::new(),
+            lengths: Vec::new(),
+            sequences: Vec::new(),
+        };
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            let fields: Vec<&str> = line.split('\t').collect();
+            ensure!(
+                fields.len() == 3 && fields[2].starts_with("SQ."),
+                "Invalid sequence digest line: '{}'",
+                line
+            );
+            collection.names.push(fields[0].to_string());
+            collection.lengths.push(fields[1].parse::<u32>()?);
+            collection.sequences.push(fields[2].to_string());
+        }
+        Ok(collection)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Level 1 digest of the `names` array.
+    pub fn names_digest(&self) -> String {
+        sha512t24u(json_array(self.names.iter().map(|name| json_string(name))).as_bytes())
+    }
+
+    /// Level 1 digest of the `lengths` array.
+    pub fn lengths_digest(&self) -> String {
+        sha512t24u(json_array(self.lengths.iter().map(|length| length.to_string())).as_bytes())
+    }
+
+    /// Level 1 digest of the `sequences` array.
+    pub fn sequences_digest(&self) -> String {
+        sha512t24u(json_array(self.sequences.iter().map(|seq| json_string(seq))).as_bytes())
+    }
+
+    /// Level 1 digest of the `sorted_name_length_pairs` array, which only
+    /// depends on the coordinate system and not on sequence order or content.
+    pub fn sorted_name_length_pairs_digest(&self) -> String {
+        let mut pairs: Vec<String> = self
+            .names
+            .iter()
+            .zip(self.lengths.iter())
+            .map(|(name, length)| {
+                sha512t24u(
+                    format!("{{\"length\":{},\"name\":{}}}", length, json_string(name)).as_bytes(),
+                )
+            })
+            .collect();
+        pairs.sort();
+        sha512t24u(json_array(pairs.iter().map(|pair| json_string(pair))).as_bytes())
+    }
+
+    /// The level 0 digest identifying the whole collection.
+    pub fn digest(&self) -> String {
+        sha512t24u(
+            format!(
+                "{{\"lengths\":{},\"names\":{},\"sequences\":{}}}",
+                json_string(&self.lengths_digest()),
+                json_string(&self.names_digest()),
+                json_string(&self.sequences_digest())
+            )
+            .as_bytes(),
+        )
+    }
+
+    /// Compare sequences by name against another collection.
+    pub fn compare(&self, other: &SequenceCollection) -> AssemblyComparison {
+        let other_seqs: HashMap<&String, (u32, &String)> = other
+            .names
+            .iter()
+            .zip(other.lengths.iter().zip(other.sequences.iter()))
+            .map(|(name, (length, seq))| (name, (*length, seq)))
+            .collect();
+
+        let mut comparison = AssemblyComparison::new(&self.names, &other.names);
+        comparison.identical = self == other;
+        for ((name, length), seq) in self
+            .names
+            .iter()
+            .zip(self.lengths.iter())
+            .zip(self.sequences.iter())
+        {
+            match other_seqs.get(name) {
+                Some((other_length, _)) if other_length != length => {
+                    comparison.length_mismatches.push(name.to_string())
+                }
+                Some((_, other_seq)) if *other_seq != seq => {
+                    comparison.sequence_mismatches.push(name.to_string())
+                }
+                _ => {}
+            }
+        }
+        comparison
+    }
+
+    /// Compare sequences by name against a chrom sizes table. Only lengths
+    /// can be checked, and the collections are never reported as identical.
+    pub fn compare_sizes(&self, chrom_sizes: &ChromSizes) -> AssemblyComparison {
+        let mut other_names: Vec<String> =
+            chrom_sizes.iter().map(|(chr, _)| chr.to_string()).collect();
+        other_names.sort();
+
+        let mut comparison = AssemblyComparison::new(&self.names, &other_names);
+        for (name, length) in self.names.iter().zip(self.lengths.iter()) {
+            if matches!(chrom_sizes.get(name), Some(size) if size != *length) {
+                comparison.length_mismatches.push(name.to_string());
+            }
+        }
+        comparison
+    }
+}
+
+/// Output of `SequenceCollection::compare`. Names are listed in the order of
+/// the collection they come from.
+#[derive(Debug, Clone)]
+pub struct AssemblyComparison {
+    pub identical: bool,
+    pub shared: Vec<String>,
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+    // shared sequences whose lengths differ
+    pub length_mismatches: Vec<String>,
+    // shared sequences of the same length whose bases differ
+    pub sequence_mismatches: Vec<String>,
+}
+
+impl AssemblyComparison {
+    fn new(a: &[String], b: &[String]) -> AssemblyComparison {
+        let a_names: HashSet<&String> = a.iter().collect();
+        let b_names: HashSet<&String> = b.iter().collect();
+        AssemblyComparison {
+            identical: false,
+            shared: a
+                .iter()
+                .filter(|name| b_names.contains(name))
+                .cloned()
+                .collect(),
+            only_in_a: a
+                .iter()
+                .filter(|name| !b_names.contains(name))
+                .cloned()
+                .collect(),
+            only_in_b: b
+                .iter()
+                .filter(|name| !a_names.contains(name))
+                .cloned()
+                .collect(),
+            length_mismatches: Vec::new(),
+            sequence_mismatches: Vec::new(),
+        }
+    }
+
+    /// True when every shared sequence matches, so coordinates on one
+    /// assembly are valid on the other.
+    pub fn is_compatible(&self) -> bool {
+        !self.shared.is_empty()
+            && self.length_mismatches.is_empty()
+            && self.sequence_mismatches.is_empty()
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+use memmap2::Mmap;
+
+use crate::models::{GenomeAssembly, Region};
+
+// UCSC .2bit signature, read back byte-swapped from big-endian files
+const SIGNATURE: u32 = 0x1A412743;
+
+// packed base codes, four bases per byte with the first in the high bits
+const PACKED_BASES: [u8; 4] = [b'T', b'C', b'A', b'G'];
+
+fn pack_code(base: u8) -> u8 {
+    match base.to_ascii_uppercase() {
+        b'C' => 1,
+        b'A' => 2,
+        b'G' => 3,
+        // T, and N which the N blocks restore
+        _ => 0,
+    }
+}
+
+// half-open runs of positions where `keep` holds, as (start, size) pairs
+fn blocks(seq: &[u8], keep: impl Fn(u8) -> bool) -> Vec<(u32, u32)> {
+    let mut blocks = Vec::new();
+    let mut start: Option<usize> = None;
+    for (i, base) in seq.iter().enumerate() {
+        match (keep(*base), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                blocks.push((s as u32, (i - s) as u32));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        blocks.push((s as u32, (seq.len() - s) as u32));
+    }
+    blocks
+}
+
+/// Writes every chromosome of `genome`, in file order, in the UCSC .2bit
+/// format. Soft-masking is kept as mask blocks. Any base other than A, C, G
+/// and T is stored as N.
+pub fn write_twobit<W: Write>(genome: &GenomeAssembly, writer: &mut W) -> Result<()> {
+    struct Record<'a> {
+        name: &'a str,
+        seq: &'a [u8],
+        n_blocks: Vec<(u32, u32)>,
+        mask_blocks: Vec<(u32, u32)>,
+    }
+
+    let mut records = Vec::new();
+    for (chr, size) in genome.chrom_lengths() {
+        ensure!(
+            chr.len() <= u8::MAX as usize,
+            "Chromosome name '{}' is longer than the 255 bytes a .2bit file allows",
+            chr
+        );
+        let seq = genome.seq_from_region(&Region::new(chr, 0, size))?;
+        records.push(Record {
+            name: chr,
+            seq,
+            n_blocks: blocks(seq, |base| {
+                !matches!(base.to_ascii_uppercase(), b'A' | b'C' | b'G' | b'T')
+            }),
+            mask_blocks: blocks(seq, |base| base.is_ascii_lowercase()),
+        });
+    }
+
+    let record_size = |record: &Record| -> u64 {
+        16 + 8 * (record.n_blocks.len() + record.mask_blocks.len()) as u64
+            + (record.seq.len() as u64).div_ceil(4)
+    };
+
+    // version 1 stores 64-bit offsets, needed once the file passes 4 GiB
+    let index_size = |offset_size: u64| -> u64 {
+        records
+            .iter()
+            .map(|record| 1 + record.name.len() as u64 + offset_size)
+            .sum()
+    };
+    let total_size = 16 + index_size(4) + records.iter().map(record_size).sum::<u64>();
+    let version: u32 = match total_size > u32::MAX as u64 {
+        true => 1,
+        false => 0,
+    };
+    let offset_size = match version {
+        1 => 8,
+        _ => 4,
+    };
+
+    writer.write_all(&SIGNATURE.to_le_bytes())?;
+    writer.write_all(&version.to_le_bytes())?;
+    writer.write_all(&(records.len() as u32).to_le_bytes())?;
+    writer.write_all(&0u32.to_le_bytes())?;
+
+    let mut offset = 16 + index_size(offset_size);
+    for record in records.iter() {
+        writer.write_all(&[record.name.len() as u8])?;
+        writer.write_all(record.name.as_bytes())?;
+        match version {
+            1 => writer.write_all(&offset.to_le_bytes())?,
+            _ => writer.write_all(&(offset as u32).to_le_bytes())?,
+        }
+        offset += record_size(record);
+    }
+
+    for record in records.iter() {
+        writer.write_all(&(record.seq.len() as u32).to_le_bytes())?;
+        for block_list in [&record.n_blocks, &record.mask_blocks] {
+            writer.write_all(&(block_list.len() as u32).to_le_bytes())?;
+            for (start, _) in block_list.iter() {
+                writer.write_all(&start.to_le_bytes())?;
+            }
+            for (_, size) in block_list.iter() {
+                writer.write_all(&size.to_le_bytes())?;
+            }
+        }
+        writer.write_all(&0u32.to_le_bytes())?;
+
+        let packed: Vec<u8> = record
+            .seq
+            .chunks(4)
+            .map(|chunk| {
+                (0..4).fold(0u8, |byte, i| {
+                    (byte << 2) | chunk.get(i).map_or(0, |base| pack_code(*base))
+                })
+            })
+            .collect();
+        writer.write_all(&packed)?;
+    }
+
+    Ok(())
+}
+
+// where a sequence lives in the file
+struct TwoBitRecord {
+    dna_size: u32,
+    // offset of the N block count
+    blocks_offset: usize,
+    // offset of the packed bases
+    dna_offset: usize,
+}
+
+/// A memory-mapped UCSC .2bit file. Opening it reads only the index, each
+/// sequence is unpacked on request.
+pub struct TwoBitFile {
+    mmap: Mmap,
+    big_endian: bool,
+    records: HashMap<String, TwoBitRecord>,
+    // sequence names in index order
+    names: Vec<String>,
+}
+
+impl TwoBitFile {
+    pub fn open(path: &Path) -> Result<TwoBitFile> {
+        let file = File::open(path)?;
+        // safety: the map is read-only and the file is not expected to change
+        // while it is open
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        ensure!(mmap.len() >= 16, "File is too short to be a .2bit file");
+        let big_endian = match u32::from_le_bytes(mmap[0..4].try_into()?) {
+            SIGNATURE => false,
+            signature if signature.swap_bytes() == SIGNATURE => true,
+            _ => return Err(anyhow::anyhow!("File is not a .2bit file")),
+        };
+
+        let mut twobit = TwoBitFile {
+            mmap,
+            big_endian,
+            records: HashMap::new(),
+            names: Vec::new(),
+        };
+
+        let version = twobit.read_u32(4)?;
+        ensure!(
+            version <= 1,
+            "Unsupported .2bit version {}, expected 0 or 1",
+            version
+        );
+        let seq_count = twobit.read_u32(8)?;
+
+        let mut pos = 16;
+        for _ in 0..seq_count {
+            let name_len = *twobit
+                .mmap
+                .get(pos)
+                .ok_or_else(|| anyhow::anyhow!("Truncated .2bit index"))?
+                as usize;
+            let name = twobit
+                .mmap
+                .get(pos + 1..pos + 1 + name_len)
+                .ok_or_else(|| anyhow::anyhow!("Truncated .2bit index"))?;
+            let name = String::from_utf8(name.to_vec())?;
+            pos += 1 + name_len;
+            ensure!(
+                !twobit.records.contains_key(&name),
+                "Duplicate chromosome found in .2bit file: {}",
+                name
+            );
+
+            let offset = match version {
+                1 => {
+                    let low = twobit.read_u32(pos)? as u64;
+                    let high = twobit.read_u32(pos + 4)? as u64;
+                    pos += 8;
+                    match twobit.big_endian {
+                        true => (low << 32) | high,
+                        false => (high << 32) | low,
+                    }
+                }
+                _ => {
+                    pos += 4;
+                    twobit.read_u32(pos - 4)? as u64
+                }
+            } as usize;
+
+            let record = twobit.read_record(offset)?;
+            twobit.names.push(name.to_string());
+            twobit.records.insert(name, record);
+        }
+
+        Ok(twobit)
+    }
+
+    fn read_u32(&self, pos: usize) -> Result<u32> {
+        let bytes: [u8; 4] = self
+            .mmap
+            .get(pos..pos + 4)
+            .ok_or_else(|| anyhow::anyhow!("Unexpected end of .2bit file at byte {}", pos))?
+            .try_into()?;
+        Ok(match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        })
+    }
+
+    fn read_record(&self, offset: usize) -> Result<TwoBitRecord> {
+        let dna_size = self.read_u32(offset)?;
+        let n_count = self.read_u32(offset + 4)? as usize;
+        let mask_pos = offset + 8 + 8 * n_count;
+        let mask_count = self.read_u32(mask_pos)? as usize;
+        let dna_offset = mask_pos + 4 + 8 * mask_count + 4;
+
+        ensure!(
+            dna_offset + (dna_size as usize).div_ceil(4) <= self.mmap.len(),
+            "Sequence at byte {} runs past the end of the .2bit file",
+            offset
+        );
+
+        Ok(TwoBitRecord {
+            dna_size,
+            blocks_offset: offset + 4,
+            dna_offset,
+        })
+    }
+
+    // (start, size) pairs of the block list starting at `pos`
+    fn read_blocks(&self, pos: usize) -> Result<Vec<(usize, usize)>> {
+        let count = self.read_u32(pos)? as usize;
+        (0..count)
+            .map(|i| {
+                Ok((
+                    self.read_u32(pos + 4 + 4 * i)? as usize,
+                    self.read_u32(pos + 4 + 4 * (count + i))? as usize,
+                ))
+            })
+            .collect()
+    }
+
+    /// Sequence names and lengths in index order.
+    pub fn sizes(&self) -> impl Iterator<Item = (&String, u32)> {
+        self.names
+            .iter()
+            .map(|name| (name, self.records[name].dna_size))
+    }
+
+    pub fn size(&self, name: &str) -> Option<u32> {
+        self.records.get(name).map(|record| record.dna_size)
+    }
+
+    /// The full sequence of `name`, with N blocks and soft-masking restored.
+    pub fn read_seq(&self, name: &str) -> Result<Vec<u8>> {
+        let record = self
+            .records
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("Sequence {} is not in the .2bit file", name))?;
+        let size = record.dna_size as usize;
+
+        let packed = &self.mmap[record.dna_offset..record.dna_offset + size.div_ceil(4)];
+        let mut seq = Vec::with_capacity(packed.len() * 4);
+        for byte in packed {
+            for shift in [6, 4, 2, 0] {
+                seq.push(PACKED_BASES[((byte >> shift) & 3) as usize]);
+            }
+        }
+        seq.truncate(size);
+
+        let n_blocks = self.read_blocks(record.blocks_offset)?;
+        let mask_pos = record.blocks_offset + 4 + 8 * n_blocks.len();
+        for (start, len) in n_blocks {
+            seq[start.min(size)..(start + len).min(size)].fill(b'N');
+        }
+        for (start, len) in self.read_blocks(mask_pos)? {
+            seq[start.min(size)..(start + len).min(size)].make_ascii_lowercase();
+        }
+
+        Ok(seq)
+    }
+}
+
+/// True when `path` starts with the .2bit signature, in either byte order.
+pub fn is_twobit(path: &Path) -> Result<bool> {
+    let mut signature = [0u8; 4];
+    let mut file = File::open(path)?;
+    match std::io::Read::read_exact(&mut file, &mut signature) {
+        Ok(()) => {
+            let signature = u32::from_le_bytes(signature);
+            Ok(signature == SIGNATURE || signature.swap_bytes() == SIGNATURE)
+        }
+        Err(_) => Ok(false),
+    }
+}
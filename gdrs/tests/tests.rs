@@ -5,7 +5,11 @@ use std::path::Path;
 
 mod tests {
 
-    use gdrs::{calc_dinucl_freq, models::GenomeAssembly};
+    use gdrs::{
+        calc_dinucl_freq,
+        models::{ChromNaming, Dinucleotide, GenomeAssembly},
+        motifs::Pwm,
+    };
 
     use super::*;
 
@@ -77,4 +81,1128 @@ mod tests {
             assert!(freq >= 0.0);
         }
     }
+
+    #[rstest]
+    fn test_chrom_sizes_from_file() {
+        let chrom_sizes = ChromSizes::from_file(Path::new("tests/data/test.chrom.sizes")).unwrap();
+        assert_eq!(chrom_sizes.len(), 7);
+        assert_eq!(chrom_sizes.get("chr1"), Some(248_956_422));
+        assert_eq!(chrom_sizes.get("chrZ"), None);
+
+        let fai = ChromSizes::from_file(Path::new("tests/data/test.fa.fai")).unwrap();
+        let fasta = ChromSizes::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+
+        for sizes in [fai, fasta, genome.chrom_sizes()] {
+            assert_eq!(sizes.get("chr1"), Some(120));
+            assert_eq!(sizes.get("chr2"), Some(40));
+            assert_eq!(sizes.total_size(), 160);
+        }
+
+        let path = std::env::temp_dir().join(format!("gdrs_test_dup_{}.fa", std::process::id()));
+        std::fs::write(&path, ">chr1\nACGT\n>chr1\nAC\n").unwrap();
+        assert!(ChromSizes::from_fasta(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[rstest]
+    fn test_chrom_sizes_validate() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test.bed")).unwrap();
+
+        let chrom_sizes = ChromSizes::from_file(Path::new("tests/data/test.chrom.sizes")).unwrap();
+        assert!(chrom_sizes.validate(&region_set).is_ok());
+
+        // every region runs past the tiny test genome or sits on a missing chromosome
+        let chrom_sizes = ChromSizes::from_file(Path::new("tests/data/test.fa.fai")).unwrap();
+        assert!(chrom_sizes.validate(&region_set).is_err());
+        assert_eq!(chrom_sizes.invalid_regions(&region_set).len(), 13);
+    }
+
+    #[rstest]
+    #[case("hg38", 25, "chr1", 248_956_422)]
+    #[case("GRCh37", 25, "1", 249_250_621)]
+    #[case("mm10", 22, "chr1", 195_471_971)]
+    #[case("GRCm39", 22, "1", 195_154_279)]
+    fn test_chrom_sizes_builtin(
+        #[case] assembly: &str,
+        #[case] n_chroms: usize,
+        #[case] chr: &str,
+        #[case] size: u32,
+    ) {
+        let chrom_sizes = ChromSizes::builtin(assembly).unwrap();
+        assert_eq!(chrom_sizes.len(), n_chroms);
+        assert_eq!(chrom_sizes.get(chr), Some(size));
+    }
+
+    #[rstest]
+    fn test_chrom_sizes_builtin_grc_naming() {
+        let chrom_sizes = ChromSizes::builtin("GRCh38").unwrap();
+        assert!(chrom_sizes.contains_chr("MT"));
+        assert!(chrom_sizes.contains_chr("X"));
+        assert!(!chrom_sizes.contains_chr("chr1"));
+
+        let region_set =
+            RegionSet::from_regions(vec![Region::new("1", 0, 100), Region::new("MT", 0, 100)]);
+        assert!(chrom_sizes.validate(&region_set).is_ok());
+    }
+
+    #[rstest]
+    fn test_chrom_sizes_builtin_unknown() {
+        assert!(ChromSizes::builtin("hg42").is_err());
+
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test.bed")).unwrap();
+        assert!(ChromSizes::builtin("hg38")
+            .unwrap()
+            .validate(&region_set)
+            .is_ok());
+    }
+
+    #[rstest]
+    fn test_detect_assembly() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test.bed")).unwrap();
+        let guess = detect_assembly(&region_set).unwrap();
+
+        // the chr17 regions run past the end of hg19's chr17
+        assert_eq!(guess.assembly, "hg38");
+        assert_eq!(guess.naming, ChromNaming::Ucsc);
+        assert!(guess.confidence > 0.99);
+        assert!(guess.incompatible_regions.is_empty());
+
+        let hg19 = guess
+            .candidates
+            .iter()
+            .find(|c| c.assembly == "hg19")
+            .unwrap();
+        assert_eq!(hg19.n_incompatible, 2);
+    }
+
+    #[rstest]
+    fn test_detect_assembly_ensembl_names() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_ensembl.bed")).unwrap();
+        let guess = detect_assembly(&region_set).unwrap();
+
+        assert_eq!(guess.assembly, "hg38");
+        assert_eq!(guess.naming, ChromNaming::Ensembl);
+        assert_eq!(guess.n_unplaced, 1);
+    }
+
+    #[rstest]
+    #[case("chr1", "chr1")]
+    #[case("1", "chr1")]
+    #[case("NC_000001.11", "chr1")]
+    #[case("NC_000067.7", "chr1")]
+    #[case("MT", "chrM")]
+    #[case("KI270706.1", "KI270706.1")]
+    fn test_chrom_aliases_builtin(#[case] chr: &str, #[case] canonical: &str) {
+        assert_eq!(ChromAliases::builtin().canonical(chr), canonical);
+    }
+
+    #[rstest]
+    fn test_chrom_aliases_genome_lookup() {
+        let aliases = ChromAliases::from_file(Path::new("tests/data/test.chromAlias.txt")).unwrap();
+        assert_eq!(aliases.canonical("CM000663.2"), "chr1");
+        assert!(aliases.same_chrom("NC_000002.12", "2"));
+
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let region = Region::new("CM000664.2", 0, 4);
+        assert!(!genome.contains_chr("CM000664.2"));
+        assert!(genome.seq_from_region(&region).is_err());
+
+        let genome = genome.with_aliases(&aliases).unwrap();
+        assert!(genome.contains_chr("CM000664.2"));
+        assert!(genome.contains_chr("1"));
+        assert_eq!(genome.seq_from_region(&region).unwrap(), b"AAAA");
+
+        // two records for the same chromosome can't both be resolved
+        let path = std::env::temp_dir().join(format!("gdrs_test_alias_{}.fa", std::process::id()));
+        std::fs::write(&path, ">chr1\nACGT\n>1\nACGT\n").unwrap();
+        let genome = GenomeAssembly::from_fasta(&path).unwrap();
+        assert!(genome.with_aliases(&ChromAliases::builtin()).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[rstest]
+    fn test_region_set_into_canonical() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_ensembl.bed"))
+            .unwrap()
+            .into_canonical(&ChromAliases::builtin());
+
+        assert!(region_set.contains_chr("chr1"));
+        assert!(!region_set.contains_chr("1"));
+        assert_eq!(region_set.iter_regions("chr17").count(), 2);
+        assert!(region_set.iter_regions("chr17").all(|r| r.chr == "chr17"));
+    }
+
+    #[rstest]
+    #[case("chr1", ChromClass::Autosome)]
+    #[case("22", ChromClass::Autosome)]
+    #[case("chr2L", ChromClass::Autosome)]
+    #[case("chrIV", ChromClass::Autosome)]
+    #[case("chrX", ChromClass::Sex)]
+    #[case("MT", ChromClass::Mito)]
+    #[case("chr1_KI270706v1_random", ChromClass::Random)]
+    #[case("chrUn_KI270302v1", ChromClass::Unplaced)]
+    #[case("GL000192.1", ChromClass::Unplaced)]
+    #[case("chr6_GL000250v2_alt", ChromClass::Alt)]
+    #[case("chr1_KN196472v1_fix", ChromClass::Fix)]
+    #[case("chrUn_JTFH01000001v1_decoy", ChromClass::Decoy)]
+    #[case("chrEBV", ChromClass::Ebv)]
+    fn test_chrom_class(#[case] chr: &str, #[case] class: ChromClass) {
+        assert_eq!(ChromClass::from_chr(chr), class);
+    }
+
+    #[rstest]
+    fn test_filter_chroms() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_ensembl.bed"))
+            .unwrap()
+            .filter_chroms(&ChromClass::CANONICAL);
+
+        assert!(!region_set.contains_chr("KI270706.1"));
+        assert!(region_set.contains_chr("1"));
+        assert!(region_set.contains_chr("NC_000017.11"));
+
+        let region_set = region_set.filter_chroms(&[ChromClass::Sex]);
+        assert!(region_set.is_empty());
+    }
+
+    #[rstest]
+    #[case(b"AC", Some(Dinucleotide::Ac))]
+    #[case(b"cg", Some(Dinucleotide::Cg))]
+    #[case(b"Tg", Some(Dinucleotide::Tg))]
+    #[case(b"Nc", None)]
+    #[case(b"AR", None)]
+    fn test_dinucleotide_from_bytes(#[case] bytes: &[u8], #[case] dinucl: Option<Dinucleotide>) {
+        assert_eq!(Dinucleotide::from_bytes(bytes), dinucl);
+    }
+
+    #[rstest]
+    fn test_dinucleotide_counts_and_freq() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_fa.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+
+        let counts = calc_dinucl_counts(&region_set, &genome).unwrap();
+        // the 11 windows touching the N run are skipped
+        assert_eq!(counts.values().sum::<u64>(), 146);
+        assert_eq!(counts[&Dinucleotide::Cg], 26);
+        assert_eq!(counts[&Dinucleotide::Aa], 14);
+
+        let freqs = calc_dinucl_freq(&region_set, &genome).unwrap();
+        let total: f64 = freqs.values().sum();
+        assert!((total - 100.0).abs() < 1e-9);
+        assert!((freqs[&Dinucleotide::Cg] - 26.0 / 146.0 * 100.0).abs() < 1e-9);
+    }
+
+    #[rstest]
+    fn test_dinucleotide_freq_per_region() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_fa.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+
+        let counts = calc_dinucl_freq_per_region(&region_set, &genome, true).unwrap();
+        assert_eq!(counts.n_rows(), 3);
+        assert_eq!(counts.n_cols(), 16);
+        assert_eq!(counts.columns[6], "CG");
+
+        // rows come back in input order
+        let starts: Vec<u32> = counts.regions.iter().map(|r| r.start).collect();
+        assert_eq!(starts, vec![0, 60, 0]);
+        assert_eq!(counts.row(0)[6], 15.0);
+        assert_eq!(counts.row(2).iter().sum::<f64>(), 39.0);
+
+        let freqs = calc_dinucl_freq_per_region(&region_set, &genome, false).unwrap();
+        for i in 0..freqs.n_rows() {
+            assert!((freqs.row(i).iter().sum::<f64>() - 100.0).abs() < 1e-9);
+        }
+    }
+
+    #[rstest]
+    #[case(1, false, 4)]
+    #[case(1, true, 2)]
+    #[case(3, true, 32)]
+    #[case(6, false, 4096)]
+    #[case(6, true, 2080)]
+    fn test_kmer_columns(#[case] k: usize, #[case] canonical: bool, #[case] n_kmers: usize) {
+        let kmer_counts = KmerCounts::new(k, canonical).unwrap();
+        assert_eq!(kmer_counts.kmers().len(), n_kmers);
+    }
+
+    #[rstest]
+    fn test_kmer_counts() {
+        assert!(KmerCounts::new(0, false).is_err());
+
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_fa.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+
+        // dinucleotides through the k-mer counter agree with calc_dinucl_counts
+        let kmer_counts =
+            calc_kmer_counts(&region_set, &genome, 2, false, MaskFilter::All).unwrap();
+        assert_eq!(kmer_counts.total(), 146);
+        assert_eq!(kmer_counts.get("CG"), Some(26));
+
+        let kmer_counts = calc_kmer_counts(&region_set, &genome, 1, true, MaskFilter::All).unwrap();
+        assert_eq!(kmer_counts.kmers(), vec!["A", "C"]);
+        assert_eq!(kmer_counts.get("T"), kmer_counts.get("A"));
+        assert_eq!(kmer_counts.total(), 150);
+
+        let kmer_counts = calc_kmer_counts(&region_set, &genome, 3, true, MaskFilter::All).unwrap();
+        assert_eq!(kmer_counts.get("ACG"), kmer_counts.get("CGT"));
+    }
+
+    #[rstest]
+    fn test_kmer_freq_per_region() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_fa.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+
+        let matrix =
+            calc_kmer_freq_per_region(&region_set, &genome, 1, false, MaskFilter::All, true)
+                .unwrap();
+        assert_eq!(matrix.columns, vec!["A", "C", "G", "T"]);
+        assert_eq!(matrix.row(2), &[10.0, 10.0, 10.0, 10.0]);
+
+        let matrix =
+            calc_kmer_freq_per_region(&region_set, &genome, 4, true, MaskFilter::All, false)
+                .unwrap();
+        assert_eq!(matrix.n_rows(), 3);
+        assert!((matrix.row(0).iter().sum::<f64>() - 100.0).abs() < 1e-9);
+    }
+
+    #[rstest]
+    fn test_calc_cpg_oe() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_fa.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+
+        let cpg_stats = calc_cpg_oe(&region_set, &genome).unwrap();
+        assert_eq!(cpg_stats.len(), 3);
+
+        // ACGT repeats: every C is followed by a G
+        assert_eq!(cpg_stats[0].cpg_count, 15);
+        assert!((cpg_stats[0].cpg_density - 0.25).abs() < 1e-9);
+        assert!((cpg_stats[0].cpg_oe - 4.0).abs() < 1e-9);
+
+        assert_eq!(cpg_stats[2].region.chr, "chr2");
+        assert_eq!(cpg_stats[2].cpg_count, 1);
+        assert!((cpg_stats[2].cpg_oe - 0.4).abs() < 1e-9);
+    }
+
+    #[rstest]
+    #[case(false, 0.5)]
+    #[case(true, 0.6)]
+    fn test_calc_gc_content_stats(#[case] exclude_ambiguous: bool, #[case] expected: f64) {
+        let region_set = RegionSet::from_regions(vec![
+            Region::new("chr1", 60, 120),
+            Region::new("chr1", 70, 70),
+            // entirely inside the N run
+            Region::new("chr1", 72, 82),
+        ]);
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let options = GcContentOptions {
+            exclude_ambiguous,
+            ..Default::default()
+        };
+
+        let gc_stats = calc_gc_content_stats(&region_set, &genome, &options).unwrap();
+        assert_eq!(gc_stats.len(), 3);
+
+        let gc = gc_stats[0].gc_content.unwrap();
+        assert!((gc - expected).abs() < 1e-9);
+        assert!((gc_stats[0].n_fraction.unwrap() - 10.0 / 60.0).abs() < 1e-9);
+
+        assert_eq!(gc_stats[1].gc_content, None);
+        assert_eq!(gc_stats[1].n_fraction, None);
+
+        assert_eq!(gc_stats[2].n_fraction, Some(1.0));
+        match exclude_ambiguous {
+            true => assert_eq!(gc_stats[2].gc_content, None),
+            false => assert_eq!(gc_stats[2].gc_content, Some(0.0)),
+        }
+    }
+
+    #[rstest]
+    fn test_calc_gc_content_stats_input_order() {
+        let region_set = RegionSet::from_regions(vec![
+            Region::new("chr2", 0, 10),
+            Region::new("chrZ", 0, 10),
+            Region::new("chr1", 0, 10),
+            Region::new("chr2", 10, 20),
+        ]);
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let options = GcContentOptions {
+            ignore_unk_chroms: true,
+            ..Default::default()
+        };
+
+        let gc_stats = calc_gc_content_stats(&region_set, &genome, &options).unwrap();
+        let regions: Vec<(&str, u32)> = gc_stats
+            .iter()
+            .map(|stats| (stats.region.chr.as_str(), stats.region.start))
+            .collect();
+        assert_eq!(regions, vec![("chr2", 0), ("chr1", 0), ("chr2", 10)]);
+    }
+
+    #[rstest]
+    #[case(false, 0.0, 0.0)]
+    #[case(true, 1.6 / 3.0 - 0.55, 1.0 / 6.0)]
+    fn test_calc_gc_content_ref_tile(
+        #[case] exclude_ambiguous: bool,
+        #[case] mean_difference: f64,
+        #[case] ks_statistic: f64,
+    ) {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_fa.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let options = GcContentOptions {
+            exclude_ambiguous,
+            ..Default::default()
+        };
+
+        // median width is 60, so chr1 holds two tiles and chr2 none
+        let gc_ref =
+            calc_gc_content_ref(&region_set, &genome, GcBackgroundMethod::Tile, &options).unwrap();
+
+        assert_eq!(gc_ref.observed.len(), 3);
+        assert_eq!(gc_ref.background.len(), 2);
+        assert!((gc_ref.mean_difference - mean_difference).abs() < 1e-9);
+        assert!((gc_ref.observed_mean - gc_ref.background_mean - mean_difference).abs() < 1e-9);
+        assert!((gc_ref.ks_statistic - ks_statistic).abs() < 1e-9);
+    }
+
+    #[rstest]
+    fn test_calc_gc_content_ref_sample() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_fa.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let method = GcBackgroundMethod::Sample {
+            n_samples: 100,
+            seed: 7,
+        };
+
+        let gc_ref =
+            calc_gc_content_ref(&region_set, &genome, method, &GcContentOptions::default())
+                .unwrap();
+        let again = calc_gc_content_ref(&region_set, &genome, method, &GcContentOptions::default())
+            .unwrap();
+
+        assert_eq!(gc_ref.background.len(), 100);
+        assert_eq!(gc_ref.background, again.background);
+        assert!(gc_ref.background.iter().all(|gc| (0.0..=1.0).contains(gc)));
+    }
+
+    #[rstest]
+    fn test_calc_repeat_fraction() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_fa.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+
+        let repeat_fractions = calc_repeat_fraction(&region_set, &genome).unwrap();
+        assert_eq!(
+            repeat_fractions,
+            vec![Some(0.0), Some(38.0 / 60.0), Some(0.0)]
+        );
+    }
+
+    #[rstest]
+    #[case(MaskFilter::All, false, 30.0 / 60.0)]
+    #[case(MaskFilter::Masked, false, 26.0 / 38.0)]
+    #[case(MaskFilter::Unmasked, false, 4.0 / 22.0)]
+    #[case(MaskFilter::Unmasked, true, 4.0 / 12.0)]
+    fn test_gc_content_mask(
+        #[case] mask: MaskFilter,
+        #[case] exclude_ambiguous: bool,
+        #[case] expected: f64,
+    ) {
+        let region_set = RegionSet::from_regions(vec![Region::new("chr1", 60, 120)]);
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let options = GcContentOptions {
+            exclude_ambiguous,
+            mask,
+            ..Default::default()
+        };
+
+        let gc_stats = calc_gc_content_stats(&region_set, &genome, &options).unwrap();
+        assert!((gc_stats[0].gc_content.unwrap() - expected).abs() < 1e-9);
+    }
+
+    #[rstest]
+    #[case(MaskFilter::All, 48)]
+    #[case(MaskFilter::Masked, 35)]
+    #[case(MaskFilter::Unmasked, 11)]
+    fn test_kmer_counts_mask(#[case] mask: MaskFilter, #[case] total: u64) {
+        let region_set = RegionSet::from_regions(vec![Region::new("chr1", 60, 120)]);
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+
+        // k-mers never cross the N run or a masked/unmasked boundary
+        let kmer_counts = calc_kmer_counts(&region_set, &genome, 2, false, mask).unwrap();
+        assert_eq!(kmer_counts.total(), total);
+    }
+
+    #[rstest]
+    fn test_from_bed_name_and_strand() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_named.bed")).unwrap();
+        let regions: Vec<&Region> = region_set.iter().collect();
+
+        assert_eq!(regions[0].name, Some("peak1".to_string()));
+        assert_eq!(regions[0].strand, Strand::Plus);
+        assert_eq!(regions[1].strand, Strand::Minus);
+        assert_eq!(regions[2].name, None);
+    }
+
+    #[rstest]
+    #[case(FastaName::Coords, false, None, vec![("chr1:0-8", "ACGTACGT"), ("chr2:16-24", "TTTTCCCC"), ("chr2:0-4", "AAAA")])]
+    #[case(FastaName::Name, true, None, vec![("peak1", "ACGTACGT"), ("peak2", "GGGGAAAA"), ("chr2:0-4(-)", "TTTT")])]
+    #[case(FastaName::Coords, false, Some(4), vec![("chr1:2-6", "GTAC"), ("chr2:18-22", "TTCC"), ("chr2:0-4", "AAAA")])]
+    fn test_extract_sequences(
+        #[case] name_by: FastaName,
+        #[case] stranded: bool,
+        #[case] resize: Option<u32>,
+        #[case] expected: Vec<(&str, &str)>,
+    ) {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_named.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let options = FastaOptions {
+            name_by,
+            stranded,
+            resize,
+        };
+
+        let records = extract_sequences(&region_set, &genome, &options).unwrap();
+        let records: Vec<(&str, &str)> = records
+            .iter()
+            .map(|(name, seq)| (name.as_str(), std::str::from_utf8(seq).unwrap()))
+            .collect();
+        assert_eq!(records, expected);
+    }
+
+    #[rstest]
+    #[case(10, 20, 4, 100, (13, 17))]
+    #[case(0, 4, 8, 100, (0, 8))]
+    #[case(36, 40, 8, 40, (32, 40))]
+    #[case(0, 4, 8, 6, (0, 6))]
+    fn test_resize_center(
+        #[case] start: u32,
+        #[case] end: u32,
+        #[case] width: u32,
+        #[case] chrom_size: u32,
+        #[case] expected: (u32, u32),
+    ) {
+        let region = Region::new("chr1", start, end).resize_center(width, chrom_size);
+        assert_eq!((region.start, region.end), expected);
+    }
+
+    #[rstest]
+    fn test_from_bed_rejects_inverted_region() {
+        let path =
+            std::env::temp_dir().join(format!("gdrs_test_inverted_{}.bed", std::process::id()));
+        std::fs::write(&path, "chr1\t20\t10\n").unwrap();
+        assert!(RegionSet::from_bed(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[rstest]
+    #[case("tests/data/motifs/test.jaspar", MotifFormat::Jaspar, vec!["MA0001.1"], vec!["GAATTC"])]
+    #[case("tests/data/motifs/test.meme", MotifFormat::Meme, vec!["EcoRI", "TTTTC"], vec!["GAATTC", "TTTTC"])]
+    #[case("tests/data/motifs/test.motif", MotifFormat::Homer, vec!["gaaaa_box"], vec!["GAAAA"])]
+    fn test_read_motifs(
+        #[case] path: &str,
+        #[case] format: MotifFormat,
+        #[case] ids: Vec<&str>,
+        #[case] consensus: Vec<&str>,
+    ) {
+        let contents = std::fs::read_to_string(path).unwrap();
+        assert_eq!(MotifFormat::detect(&contents), format);
+
+        let motifs = Motif::from_file(Path::new(path), None).unwrap();
+        assert_eq!(
+            motifs.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(),
+            ids
+        );
+        assert_eq!(
+            motifs.iter().map(|m| m.consensus()).collect::<Vec<_>>(),
+            consensus
+        );
+    }
+
+    #[rstest]
+    fn test_pwm_p_value() {
+        let motifs = Motif::from_file(Path::new("tests/data/motifs/test.jaspar"), None).unwrap();
+        let pwm = Pwm::new(&motifs[0]);
+
+        // only the exact site reaches the best score
+        assert!((pwm.p_value(pwm.max_score()) - 0.25f64.powi(6)).abs() < 1e-12);
+        assert_eq!(
+            pwm.p_value(pwm.threshold(1e-3)),
+            pwm.p_value(pwm.max_score())
+        );
+        assert!(pwm.threshold(1e-6) > pwm.max_score());
+    }
+
+    #[rstest]
+    fn test_pwm_scan_strands() {
+        // A or T at both positions
+        let motif = Motif::new("WW", None, vec![[1.0, 0.0, 0.0, 1.0]; 2]).unwrap();
+        let pwm = Pwm::new(&motif);
+        let best = pwm.max_score();
+
+        // AA and its reverse complement TT are different sites
+        assert_eq!(
+            pwm.scan(b"AA", best),
+            vec![(0, Strand::Plus, best), (0, Strand::Minus, best)]
+        );
+        // AT is its own reverse complement, so it is one site
+        assert_eq!(pwm.scan(b"AT", best), vec![(0, Strand::Plus, best)]);
+
+        // A or T, then A: TT only matches on the minus strand
+        let motif =
+            Motif::new("WA", None, vec![[1.0, 0.0, 0.0, 1.0], [1.0, 0.0, 0.0, 0.0]]).unwrap();
+        let pwm = Pwm::new(&motif);
+        let best = pwm.max_score();
+        assert_eq!(pwm.scan(b"TT", best), vec![(0, Strand::Minus, best)]);
+        assert_eq!(pwm.scan(b"AA", best), vec![(0, Strand::Plus, best)]);
+    }
+
+    #[rstest]
+    fn test_pwm_meme_background() {
+        let contents = std::fs::read_to_string("tests/data/motifs/test.meme")
+            .unwrap()
+            .replace("A 0.25 C 0.25 G 0.25 T 0.25", "A 0.3 C 0.2\nG 0.2 T 0.3");
+        let motifs = Motif::parse(&contents, Some(MotifFormat::Meme)).unwrap();
+        assert_eq!(motifs[0].background(), &[0.3, 0.2, 0.2, 0.3]);
+
+        // the exact GAATTC site is as likely as its bases under the background
+        let pwm = Pwm::new(&motifs[0]);
+        let expected = 0.2 * 0.3 * 0.3 * 0.3 * 0.3 * 0.2;
+        assert!((pwm.p_value(pwm.max_score()) - expected).abs() < 1e-12);
+
+        let invalid = contents.replace("A 0.3 C 0.2", "A 0.3 X 0.2");
+        assert!(Motif::parse(&invalid, Some(MotifFormat::Meme)).is_err());
+    }
+
+    #[rstest]
+    fn test_scan_motifs() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_fa.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let mut motifs = Motif::from_file(Path::new("tests/data/motifs/test.meme"), None).unwrap();
+        motifs.extend(Motif::from_file(Path::new("tests/data/motifs/test.motif"), None).unwrap());
+
+        let scan = scan_motifs(&region_set, &genome, &motifs, 1e-3).unwrap();
+        assert_eq!(scan.motifs, vec!["EcoRI", "TTTTC", "gaaaa_box"]);
+
+        // GAATTC is palindromic, so each site is only counted once, on the plus strand
+        assert_eq!(scan.counts.row(0), &[0.0, 0.0, 0.0]);
+        assert_eq!(scan.counts.row(1), &[2.0, 0.0, 0.0]);
+        assert_eq!(scan.counts.row(2), &[0.0, 1.0, 1.0]);
+
+        let starts: Vec<(u32, Strand)> = scan
+            .hits
+            .iter()
+            .filter(|hit| hit.motif_index == 0)
+            .map(|hit| (hit.start, hit.strand))
+            .collect();
+        assert_eq!(starts, vec![(94, Strand::Plus), (100, Strand::Plus)]);
+
+        let hit = scan.hits.iter().find(|hit| hit.motif_index == 2).unwrap();
+        assert_eq!((hit.chr.as_str(), hit.start, hit.end), ("chr2", 16, 21));
+        assert_eq!(hit.strand, Strand::Minus);
+    }
+
+    #[rstest]
+    fn test_sample_gc_matched_background() {
+        let region_set = RegionSet::from_regions(vec![Region::new("chr1", 90, 110)]);
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+
+        let background = sample_gc_matched_background(&region_set, &genome, 2, 0.05, 1).unwrap();
+        let again = sample_gc_matched_background(&region_set, &genome, 2, 0.05, 1).unwrap();
+        assert_eq!(background.len(), 2);
+        assert!(background.iter().eq(again.iter()));
+
+        // the query is the only 40% GC window on chr1, so it is never drawn
+        // and only chr2:8-28 is left
+        for region in background.iter() {
+            assert_eq!(
+                (region.chr.as_str(), region.start, region.end),
+                ("chr2", 8, 28)
+            );
+        }
+    }
+
+    #[rstest]
+    fn test_calc_motif_enrichment() {
+        // chr1:72-82 is all N, so it has no background and is not counted
+        let region_set = RegionSet::from_regions(vec![
+            Region::new("chr1", 72, 82),
+            Region::new("chr1", 90, 110),
+        ]);
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let motifs = Motif::from_file(Path::new("tests/data/motifs/test.meme"), None).unwrap();
+        let options = MotifEnrichmentOptions {
+            p_value: 1e-3,
+            ..Default::default()
+        };
+
+        let enrichment = calc_motif_enrichment(&region_set, &genome, &motifs, &options).unwrap();
+        assert_eq!(enrichment.len(), 2);
+
+        let ecori = &enrichment[0];
+        assert_eq!((ecori.query_hits, ecori.query_total), (1, 1));
+        assert_eq!((ecori.background_hits, ecori.background_total), (0, 2));
+        assert_eq!(ecori.fold_enrichment, f64::INFINITY);
+        assert!((ecori.p_value - 1.0 / 3.0).abs() < 1e-9);
+        assert!((ecori.q_value - 2.0 / 3.0).abs() < 1e-9);
+
+        assert_eq!(enrichment[1].query_hits, 0);
+        assert_eq!(enrichment[1].fold_enrichment, 0.0);
+        assert!((enrichment[1].p_value - 1.0).abs() < 1e-9);
+    }
+
+    #[rstest]
+    #[case("CANNTG", true)]
+    #[case("gaattc", true)]
+    #[case("G{3,}[ACGT]{1,7}G{3,}", true)]
+    #[case("(GAATTC|GGATCC)", true)]
+    #[case("[^G]AATT", true)]
+    #[case("CA.TG", false)]
+    #[case("CA[NT", false)]
+    #[case("A*", false)]
+    #[case("", false)]
+    fn test_pattern_new(#[case] pattern: &str, #[case] valid: bool) {
+        assert_eq!(Pattern::new(pattern).is_ok(), valid);
+    }
+
+    #[rstest]
+    fn test_search_patterns() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_fa.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let patterns: Vec<Pattern> = ["GAATTC", "TTTTC", "GAAAA", "A{5,}", "WWWW"]
+            .iter()
+            .map(|p| Pattern::new(p).unwrap())
+            .collect();
+
+        let scan = search_patterns(&region_set, &genome, &patterns, true).unwrap();
+
+        // palindromic sites count once, IUPAC strings count overlapping matches
+        assert_eq!(scan.counts.row(0), &[0.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(scan.counts.row(1), &[2.0, 0.0, 0.0, 0.0, 11.0]);
+        assert_eq!(scan.counts.row(2), &[0.0, 1.0, 1.0, 2.0, 17.0]);
+
+        let gaaaa = scan.hits.iter().find(|hit| hit.pattern_index == 2).unwrap();
+        assert_eq!(
+            (gaaaa.start, gaaaa.end, gaaaa.strand),
+            (16, 21, Strand::Minus)
+        );
+
+        let a_runs: Vec<(u32, u32, Strand)> = scan
+            .hits
+            .iter()
+            .filter(|hit| hit.pattern_index == 3)
+            .map(|hit| (hit.start, hit.end, hit.strand))
+            .collect();
+        assert_eq!(a_runs, vec![(0, 10, Strand::Plus), (10, 20, Strand::Minus)]);
+
+        let plus_only = search_patterns(&region_set, &genome, &patterns, false).unwrap();
+        assert_eq!(plus_only.counts.row(2), &[0.0, 1.0, 0.0, 1.0, 17.0]);
+    }
+
+    #[rstest]
+    fn test_calc_complexity() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_fa.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+
+        let complexity_stats = calc_complexity(&region_set, &genome, 1).unwrap();
+        assert_eq!(complexity_stats.len(), 3);
+
+        // the cgcg... tail is the only dinucleotide repeat, the N run breaks homopolymers
+        assert_eq!(complexity_stats[1].longest_homopolymer, 2);
+        assert!((complexity_stats[1].dinucleotide_repeat_fraction - 14.0 / 60.0).abs() < 1e-9);
+
+        // A10 T10 C10 G10: equal base frequencies, four runs of 8 identical triplets
+        assert!((complexity_stats[2].entropy - 2.0).abs() < 1e-9);
+        assert!((complexity_stats[2].dust_score - 112.0 / 37.0).abs() < 1e-9);
+        assert_eq!(complexity_stats[2].longest_homopolymer, 10);
+        assert_eq!(complexity_stats[2].dinucleotide_repeat_fraction, 0.0);
+
+        let empty = RegionSet::from_regions(vec![Region::new("chr1", 10, 10)]);
+        let complexity_stats = calc_complexity(&empty, &genome, 1).unwrap();
+        assert!(complexity_stats[0].entropy.is_nan());
+        assert!(complexity_stats[0].dust_score.is_nan());
+    }
+
+    #[rstest]
+    fn test_one_hot_encode() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_named.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+
+        // widths 8, 8 and 4
+        assert!(one_hot_encode(&region_set, &genome, &OneHotOptions::default()).is_err());
+
+        let options = OneHotOptions {
+            resize: Some(4),
+            stranded: true,
+            reverse_complement: true,
+        };
+        let one_hot = one_hot_encode(&region_set, &genome, &options).unwrap();
+        assert_eq!(one_hot.n_seqs, 6);
+        assert_eq!(one_hot.width, 4);
+        assert_eq!(one_hot.values.len(), 6 * 4 * 4);
+
+        let decode = |i: usize| -> String {
+            one_hot.values[i * 16..(i + 1) * 16]
+                .chunks(4)
+                .map(|channels| match channels.iter().position(|c| *c == 1) {
+                    Some(channel) => b"ACGT"[channel] as char,
+                    None => 'N',
+                })
+                .collect()
+        };
+        // chr2 16-24 and chr2 0-4 are on the minus strand
+        let expected = ["GTAC", "GGAA", "TTTT", "GTAC", "TTCC", "AAAA"];
+        for (i, seq) in expected.iter().enumerate() {
+            assert_eq!(decode(i), *seq);
+        }
+
+        // N bases encode as all zeros
+        let region_set = RegionSet::from_regions(vec![Region::new("chr1", 70, 74)]);
+        let one_hot = one_hot_encode(&region_set, &genome, &OneHotOptions::default()).unwrap();
+        assert_eq!(one_hot.values[..8], [0, 1, 0, 0, 0, 1, 0, 0]);
+        assert!(one_hot.values[8..].iter().all(|v| *v == 0));
+    }
+
+    #[rstest]
+    #[case(12, vec![("chr1", 1, 69), ("chr1", 99, 120)])]
+    #[case(40, vec![("chr1", 1, 69)])]
+    fn test_find_cpg_islands(#[case] min_length: u32, #[case] expected: Vec<(&str, u32, u32)>) {
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let criteria = CpgIslandCriteria {
+            min_length,
+            ..Default::default()
+        };
+
+        let islands = find_cpg_islands(&genome, None, &criteria).unwrap();
+        let islands: Vec<(&str, u32, u32)> = islands
+            .iter()
+            .map(|r| (r.chr.as_str(), r.start, r.end))
+            .collect();
+        assert_eq!(islands, expected);
+    }
+
+    #[rstest]
+    fn test_find_cpg_islands_in_regions() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_fa.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let criteria = CpgIslandCriteria {
+            min_length: 12,
+            ..Default::default()
+        };
+
+        // islands are clipped to the regions and trimmed back to a CpG
+        let islands = find_cpg_islands(&genome, Some(&region_set), &criteria).unwrap();
+        let mut bed = Vec::new();
+        islands.write_bed(&mut bed).unwrap();
+        assert_eq!(
+            String::from_utf8(bed).unwrap(),
+            "chr1\t1\t59\nchr1\t99\t120\n"
+        );
+
+        assert_eq!(
+            "takai-jones".parse::<CpgIslandCriteria>().unwrap(),
+            CpgIslandCriteria::takai_jones()
+        );
+        assert!("ucsc".parse::<CpgIslandCriteria>().is_err());
+    }
+
+    #[rstest]
+    #[case(1, 1)]
+    #[case(10, 1)]
+    #[case(11, 0)]
+    fn test_find_gaps(#[case] min_length: u32, #[case] expected: usize) {
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+
+        let gaps = find_gaps(&genome, min_length).unwrap();
+        assert_eq!(gaps.len(), expected);
+        for gap in gaps.iter() {
+            assert_eq!((gap.chr.as_str(), gap.start, gap.end), ("chr1", 72, 82));
+        }
+    }
+
+    #[rstest]
+    #[case("HindIII", "AAGCTT", 1)]
+    #[case("dpnii", "GATC", 0)]
+    #[case("G^ANTC", "GANTC", 1)]
+    #[case("CATG^", "CATG", 4)]
+    fn test_restriction_enzyme(#[case] spec: &str, #[case] site: &str, #[case] cut: usize) {
+        let enzyme = RestrictionEnzyme::from_name_or_site(spec).unwrap();
+        assert_eq!(enzyme.site(), site);
+        assert_eq!(enzyme.cut_offset(), cut);
+    }
+
+    #[rstest]
+    fn test_digest_genome() {
+        let region_set = RegionSet::from_bed(Path::new("tests/data/test_fa.bed")).unwrap();
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let enzymes = vec![
+            RestrictionEnzyme::from_name_or_site("EcoRI").unwrap(),
+            RestrictionEnzyme::from_name_or_site("MseI").unwrap(),
+        ];
+
+        // EcoRI cuts G^AATTC at 95 and 101, MseI cuts T^TAA at 85 and 89
+        let fragments = digest_genome(&genome, &enzymes, &ChromClass::ALL).unwrap();
+        let bounds: Vec<(&str, u32, u32)> = fragments
+            .iter()
+            .map(|r| (r.chr.as_str(), r.start, r.end))
+            .collect();
+        assert_eq!(
+            bounds,
+            vec![
+                ("chr1", 0, 85),
+                ("chr1", 85, 89),
+                ("chr1", 89, 95),
+                ("chr1", 95, 101),
+                ("chr1", 101, 120),
+                ("chr2", 0, 40),
+            ]
+        );
+        assert_eq!(fragments.iter().last().unwrap().name.as_deref(), Some("5"));
+
+        let mapping = map_to_fragments(&region_set, &fragments);
+        assert_eq!(mapping, vec![vec![0], vec![0, 1, 2, 3, 4], vec![5]]);
+
+        let lengths: Vec<u32> = fragments.iter().map(|r| r.width()).collect();
+        let summary = LengthSummary::from_lengths(&lengths);
+        assert_eq!(
+            (summary.n, summary.min, summary.max),
+            (6, Some(4), Some(85))
+        );
+        assert_eq!(summary.median, 12.5);
+        assert!(LengthSummary::from_lengths(&[]).median.is_nan());
+
+        // only the kept chromosome classes are digested
+        let fragments = digest_genome(&genome, &enzymes, &[ChromClass::Sex]).unwrap();
+        assert!(fragments.is_empty());
+    }
+
+    #[rstest]
+    #[case("2bit")]
+    #[case("mmap")]
+    fn test_pack_round_trip(#[case] format: &str) {
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let path =
+            std::env::temp_dir().join(format!("gdrs_test_{}.{}", std::process::id(), format));
+
+        let mut file = std::fs::File::create(&path).unwrap();
+        match format {
+            "mmap" => write_mapped(&genome, &mut file).unwrap(),
+            _ => write_twobit(&genome, &mut file).unwrap(),
+        }
+        drop(file);
+
+        let packed = GenomeAssembly::from_file(&path).unwrap();
+        assert_eq!(packed.chrom_sizes().get("chr1"), Some(120));
+        assert_eq!(packed.chrom_sizes().get("chr2"), Some(40));
+
+        // soft-masking and the N run survive packing
+        for (chr, size) in [("chr1", 120), ("chr2", 40)] {
+            let region = Region::new(chr, 0, size);
+            assert_eq!(
+                packed.seq_from_region(&region).unwrap(),
+                genome.seq_from_region(&region).unwrap()
+            );
+        }
+        assert!(packed.seq_from_region(&Region::new("chr3", 0, 1)).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[rstest]
+    #[case("2bit")]
+    #[case("mmap")]
+    fn test_packed_duplicate_names(#[case] format: &str) {
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let mut bytes = Vec::new();
+        match format {
+            "mmap" => write_mapped(&genome, &mut bytes).unwrap(),
+            _ => write_twobit(&genome, &mut bytes).unwrap(),
+        }
+
+        // rename chr2 to chr1 in the index
+        let at = bytes.windows(4).position(|w| w == b"chr2").unwrap();
+        bytes[at + 3] = b'1';
+
+        let path = std::env::temp_dir().join(format!(
+            "gdrs_test_duplicate_{}.{}",
+            std::process::id(),
+            format
+        ));
+        std::fs::write(&path, bytes).unwrap();
+        let err = GenomeAssembly::from_file(&path).err().unwrap();
+        assert!(err.to_string().contains("Duplicate chromosome"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[rstest]
+    fn test_pack_genome() {
+        let dir = std::env::temp_dir().join(format!("gdrs_test_pack_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = Path::new("tests/data/test.fa");
+        let output = dir.join("test.2bit");
+
+        let genome = GenomeAssembly::from_fasta(input).unwrap();
+        pack_genome(&genome, input, &output, GenomeFormat::TwoBit).unwrap();
+        let digests = SequenceCollection::from_tsv(&dir.join("test.2bit.seqcol")).unwrap();
+        assert_eq!(digests, SequenceCollection::from_genome(&genome).unwrap());
+
+        // packing onto the mapped input is refused and leaves it intact
+        let packed = GenomeAssembly::from_file(&output).unwrap();
+        assert!(pack_genome(&packed, &output, &output, GenomeFormat::Mapped).is_err());
+        assert_eq!(
+            packed.seq_from_region(&Region::new("chr2", 0, 4)).unwrap(),
+            b"AAAA"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[rstest]
+    #[case("count", u32::MAX, 0)]
+    #[case("offset", 1, u64::MAX)]
+    fn test_mapped_corrupt_header(#[case] case: &str, #[case] count: u32, #[case] offset: u64) {
+        let mut bytes = b"GDRSMMAP".to_vec();
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend(count.to_le_bytes());
+        bytes.extend(1u32.to_le_bytes());
+        bytes.push(b'a');
+        bytes.extend(offset.to_le_bytes());
+        bytes.extend(10u64.to_le_bytes());
+
+        let path = std::env::temp_dir().join(format!(
+            "gdrs_test_corrupt_{}_{}.mmap",
+            std::process::id(),
+            case
+        ));
+        std::fs::write(&path, bytes).unwrap();
+        assert!(GenomeAssembly::from_file(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[rstest]
+    #[case(b"ACGT", "SQ.aKF498dAxcJAqme6QYQ7EZ07-fiw8Kw2")]
+    #[case(b"acgt", "SQ.aKF498dAxcJAqme6QYQ7EZ07-fiw8Kw2")]
+    #[case(b"", "SQ.z4PhNX7vuL3xVChQ1m2AB9Yg5AULVxXc")]
+    fn test_refget_digest(#[case] seq: &[u8], #[case] expected: &str) {
+        assert_eq!(refget_digest(seq), expected);
+    }
+
+    #[rstest]
+    fn test_sequence_collection() {
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let collection = SequenceCollection::from_genome(&genome).unwrap();
+
+        assert_eq!(collection.names, vec!["chr1", "chr2"]);
+        assert_eq!(collection.lengths, vec![120, 40]);
+        assert_eq!(
+            collection.sequences,
+            vec![
+                "SQ.YPNyVCjkJ2j6L1yaxwWqgjcSluxXIZ0b",
+                "SQ.nc5xE6DfYa3GjlhaEntfLOnspto86DbX"
+            ]
+        );
+        assert_eq!(
+            collection.names_digest(),
+            "XEsH8IMZ09CBX17iXEWRagH50VGfARLo"
+        );
+        assert_eq!(
+            collection.sorted_name_length_pairs_digest(),
+            "s2dmES3O8azF18cp84lhmWkscit4gEY9"
+        );
+        assert_eq!(collection.digest(), "cHIo5dW40Xa2UJxidyvjMkC8aY3IWOW5");
+    }
+
+    #[rstest]
+    #[case("tests/data/test.chrom.sizes", true)]
+    #[case("tests/data/test.fa", false)]
+    #[case("tests/data/test.fa.fai", true)]
+    fn test_is_chrom_sizes(#[case] path: &str, #[case] expected: bool) {
+        assert_eq!(
+            ChromSizes::is_chrom_sizes(Path::new(path)).unwrap(),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn test_sequence_collection_digests_file() {
+        let dir = std::env::temp_dir().join(format!("gdrs_test_seqcol_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.fa");
+
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let collection = SequenceCollection::from_genome(&genome).unwrap();
+        collection.write_digests(&path).unwrap();
+
+        let digests = SequenceCollection::digests_path(&path);
+        assert_eq!(digests, dir.join("test.fa.seqcol"));
+        assert_eq!(SequenceCollection::from_tsv(&digests).unwrap(), collection);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[rstest]
+    fn test_compare_assemblies() {
+        let genome = GenomeAssembly::from_fasta(Path::new("tests/data/test.fa")).unwrap();
+        let collection = SequenceCollection::from_genome(&genome).unwrap();
+
+        let same = collection.compare(&collection);
+        assert!(same.identical);
+        assert!(same.is_compatible());
+
+        // same coordinates but a different base on chr2, and an extra contig
+        let mut other = collection.clone();
+        other.sequences[1] = refget_digest(b"N");
+        other.names.push("chrUn".to_string());
+        other.lengths.push(10);
+        other.sequences.push(refget_digest(b"ACGTACGTAC"));
+        let comparison = collection.compare(&other);
+        assert!(!comparison.identical);
+        assert!(!comparison.is_compatible());
+        assert_eq!(comparison.shared, vec!["chr1", "chr2"]);
+        assert_eq!(comparison.only_in_b, vec!["chrUn"]);
+        assert_eq!(comparison.sequence_mismatches, vec!["chr2"]);
+        assert!(comparison.length_mismatches.is_empty());
+
+        let sizes = ChromSizes::from_file(Path::new("tests/data/test.chrom.sizes")).unwrap();
+        let comparison = collection.compare_sizes(&sizes);
+        assert_eq!(comparison.only_in_a, vec!["chr2"]);
+        assert_eq!(comparison.only_in_b.len(), 6);
+        assert_eq!(comparison.length_mismatches, vec!["chr1"]);
+        assert!(!comparison.is_compatible());
+    }
+
+    #[rstest]
+    #[case(false, "XCAF4wrx_O9bpIyFXKWvxoRIe_KfuMTh")]
+    #[case(true, "NNcRbgvIWtQVaLL0xXALLUiKl-BEtJsi")]
+    fn test_region_set_digest(#[case] include_names: bool, #[case] expected: &str) {
+        let named = |chr: &str, start: u32, end: u32, name: &str| Region {
+            name: Some(name.to_string()),
+            ..Region::new(chr, start, end)
+        };
+        let region_set =
+            RegionSet::from_regions(vec![named("chr2", 5, 9, "b"), named("chr1", 10, 20, "a")]);
+        assert_eq!(region_set.digest(include_names), expected);
+
+        // region order and file layout don't matter
+        let reordered =
+            RegionSet::from_regions(vec![named("chr1", 10, 20, "a"), named("chr2", 5, 9, "b")]);
+        assert_eq!(reordered.digest(include_names), expected);
+
+        let path = std::env::temp_dir().join(format!(
+            "gdrs_test_digest_{}_{}.bed",
+            std::process::id(),
+            include_names
+        ));
+        std::fs::write(
+            &path,
+            "track name=test\n# comment\nchr1  10  20  a\r\n\nchr2 05 9 b  \n",
+        )
+        .unwrap();
+        let from_file = RegionSet::from_bed(&path).unwrap();
+        assert_eq!(from_file.digest(include_names), expected);
+        std::fs::remove_file(&path).unwrap();
+
+        // names only count when asked for
+        let renamed =
+            RegionSet::from_regions(vec![named("chr1", 10, 20, "z"), named("chr2", 5, 9, "b")]);
+        assert_eq!(renamed.digest(include_names) == expected, !include_names);
+    }
 }